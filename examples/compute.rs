@@ -121,7 +121,7 @@ fn main() {
         let p = particles.len();
 
         computer.compute(p as u32, 1, 1, &mut particles);
-        shader.draw(&mut context, DrawMode::Points, p, Attribute::Array(particles.as_attrib_array()));
+        shader.draw(&mut context, DrawMode::Points, p, Attribute::Array(particles.as_attrib_array())).unwrap();
 
         // ::std::thread::sleep(::std::time::Duration::from_millis(300));
 
@@ -167,10 +167,10 @@ fn main() {
 
         if flip {
             computer.compute(num as u32, 1, 1, p1, p2);
-            shader.draw(&mut context, DrawMode::Points, num, Particle::get_attributes(p1).0);
+            shader.draw(&mut context, DrawMode::Points, num, Particle::get_attributes(p1).0).unwrap();
         } else {
             computer.compute(num as u32, 1, 1, p2, p1);
-            shader.draw(&mut context, DrawMode::Points, num, Particle::get_attributes(p2).0);
+            shader.draw(&mut context, DrawMode::Points, num, Particle::get_attributes(p2).0).unwrap();
         }
 
         flip = !flip;
@@ -111,7 +111,7 @@ fn main() {
         mat = [[theta.cos(),theta.sin(),0.0,0.0],[-theta.sin(),theta.cos(),0.0,0.0],[0.0,0.0,1.0,0.0],[0.0,0.0,0.0,1.0]];
         trans.update_data(mat.into());
 
-        shader.draw(&mut context, DrawMode::Triangles, 3, &mut trans, Attribute::Array(triangle.as_attrib_array()));
+        shader.draw(&mut context, DrawMode::Triangles, 3, &mut trans, Attribute::Array(triangle.as_attrib_array())).unwrap();
 
         println!("{:?}", ::std::time::Instant::now()-start);
 
@@ -212,18 +212,18 @@ fn main() {
             if n==0 {
                 return;
             } else if n==4 {
-                c2.compute((buf.len()>>4) as u32, 1, 1, buf);
+                c2.dispatch_threads_with_barrier(MemoryBarrier::SHADER_STORAGE, (buf.len()>>1) as u32, 1, 1, buf).unwrap();
             } else {
                 bitonic_sort(n-1, c, c2, buf);
 
                 *c.order = n-1;
                 c.flip.set(true);
-                c.compute((buf.len()>>1) as u32, 1, 1, buf);
+                c.dispatch_threads_with_barrier(MemoryBarrier::SHADER_STORAGE, (buf.len()>>1) as u32, 1, 1, buf).unwrap();
 
                 c.flip.set(false);
                 for m in (0..n-1).rev() {
                     *c.order = m;
-                    c.compute((buf.len()>>1) as u32, 1, 1, buf);
+                    c.dispatch_threads_with_barrier(MemoryBarrier::SHADER_STORAGE, (buf.len()>>1) as u32, 1, 1, buf).unwrap();
                 }
             }
 
@@ -143,15 +143,18 @@ pub struct Buffer<T:?Sized, A:BufferAccess> {
 }
 
 impl BufferTarget {
-    unsafe fn bind<T:?Sized, A:BufferAccess>(self, buf: &Buffer<T, A>) { gl::BindBuffer(self as GLenum, buf.id); }
-    unsafe fn unbind(self) { gl::BindBuffer(self as GLenum, 0); }
+    #[inline] pub unsafe fn bind<T:?Sized, A:BufferAccess>(self, buf: &Buffer<T, A>) { gl::BindBuffer(self as GLenum, buf.id); }
+    #[inline] pub unsafe fn unbind(self) { gl::BindBuffer(self as GLenum, 0); }
 }
 
 impl IndexedBufferTarget {
     pub(crate) unsafe fn bind_range<L:BlockLayout, T:?Sized+Layout<L>, A:BufferAccess>(self, buf: &Buffer<T, A>, binding: GLuint) {
         gl::BindBufferRange(self as GLenum, binding, buf.id, buf.offset, buf.size);
     }
-    pub(crate) unsafe fn unbind(self, binding: GLuint) {
+    pub unsafe fn bind_buffer<T:?Sized, A:BufferAccess>(self, buf: &Buffer<T, A>, binding: GLuint) {
+        gl::BindBufferRange(self as GLenum, binding, buf.id, buf.offset, buf.size);
+    }
+    pub unsafe fn unbind(self, binding: GLuint) {
         gl::BindBufferBase(self as GLenum, binding, 0);
     }
 }
@@ -915,6 +918,7 @@ pub struct AttribArray<'a, A:GLSLType> {
     format: A::AttributeFormat,
     stride: usize,
     offset: usize,
+    divisor: GLuint,
     p: PhantomData<A>
 }
 
@@ -931,6 +935,7 @@ impl<T:Copy, A:BufferAccess> Buffer<[T],A> {
             format: U::format(),
             stride: size_of::<T>(),
             offset: self.offset as usize + offset,
+            divisor: 0,
             p: PhantomData
         }
     }
@@ -1011,5 +1016,27 @@ impl<'a, A:GLSLType> AttribArray<'a,A> {
     #[inline] pub fn format(&self) -> A::AttributeFormat { self.format }
     #[inline] pub fn stride(&self) -> usize { self.stride }
     #[inline] pub fn offset(&self) -> usize { self.offset }
+    #[inline] pub fn divisor(&self) -> GLuint { self.divisor }
+    #[inline] pub(crate) fn buffer(&self) -> &'a Buffer<[u8], CopyOnly> { self.buf }
+
+    ///The layout of this array within its buffer, for use with a vertex format from [AttribBinding]
+    #[inline]
+    pub fn binding(&self, binding: GLuint) -> AttribBinding<A> {
+        AttribBinding {
+            format: self.format,
+            relative_offset: self.offset - self.buf.offset as usize,
+            binding: binding
+        }
+    }
+
+    ///
+    ///Makes this array advance once every `divisor` instances instead of once per vertex
+    ///
+    ///A divisor of zero makes it a regular per-vertex array again
+    ///
+    #[inline]
+    pub fn per_instance(self, divisor: GLuint) -> Self {
+        AttribArray { divisor: divisor, ..self }
+    }
 
 }
@@ -0,0 +1,221 @@
+use super::*;
+
+use std::sync::atomic::{AtomicUsize, AtomicU32, Ordering};
+
+//ARB_compute_variable_group_size isn't part of the generated bindings, so it's loaded separately
+pub const MAX_COMPUTE_VARIABLE_GROUP_INVOCATIONS_ARB: GLenum = 0x9344;
+pub const MAX_COMPUTE_VARIABLE_GROUP_SIZE_ARB: GLenum = 0x9345;
+
+type DispatchComputeGroupSizeFn = extern "system" fn(GLuint, GLuint, GLuint, GLuint, GLuint, GLuint);
+
+static DISPATCH_COMPUTE_GROUP_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn load_with<F: FnMut(&'static str) -> *const GLvoid>(mut proc_addr: F) {
+    let ptr = proc_addr("glDispatchComputeGroupSizeARB");
+    DISPATCH_COMPUTE_GROUP_SIZE.store(ptr as usize, Ordering::Relaxed);
+}
+
+fn dispatch_compute_group_size() -> Result<DispatchComputeGroupSizeFn, GLError> {
+    match DISPATCH_COMPUTE_GROUP_SIZE.load(Ordering::Relaxed) {
+        0 => Err(GLError::FunctionNotLoaded("glDispatchComputeGroupSizeARB")),
+        ptr => Ok(unsafe { ::std::mem::transmute::<usize, DispatchComputeGroupSizeFn>(ptr) })
+    }
+}
+
+unsafe fn get_indexed(pname: GLenum, i: GLuint) -> GLuint {
+    let mut val = 0;
+    gl::GetIntegeri_v(pname, i, &mut val as *mut GLint);
+    val as GLuint
+}
+
+//the limits are checked on every dispatch, so they're only queried the first time they're needed
+//(every implementation allows at least one of each, so zero means not queried yet)
+static MAX_WORK_GROUP_COUNT: [AtomicU32; 3] = [AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)];
+static MAX_VARIABLE_GROUP_SIZE: [AtomicU32; 4] = [
+    AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)
+];
+
+fn cached<const N: usize>(cache: &[AtomicU32; N], query: impl FnOnce() -> [GLuint; N]) -> [GLuint; N] {
+    let vals = cache.each_ref().map(|v| v.load(Ordering::Relaxed));
+    if !vals.contains(&0) { return vals; }
+
+    let vals = query();
+    for (v, val) in cache.iter().zip(vals) { v.store(val, Ordering::Relaxed); }
+    vals
+}
+
+///The most work groups that can be dispatched in each dimension
+pub fn max_work_group_count() -> [GLuint; 3] {
+    cached(&MAX_WORK_GROUP_COUNT, || unsafe { [0, 1, 2].map(|i| get_indexed(gl::MAX_COMPUTE_WORK_GROUP_COUNT, i)) })
+}
+
+///
+///The largest local size allowed in each dimension for programs with a variable work group size,
+///along with the most invocations allowed in a single group
+///
+pub fn max_variable_group_size() -> ([GLuint; 3], GLuint) {
+    let [x, y, z, invocations] = cached(&MAX_VARIABLE_GROUP_SIZE, || unsafe {
+        let mut invocations = 0;
+        gl::GetIntegerv(MAX_COMPUTE_VARIABLE_GROUP_INVOCATIONS_ARB, &mut invocations as *mut GLint);
+        let [x, y, z] = [0, 1, 2].map(|i| get_indexed(MAX_COMPUTE_VARIABLE_GROUP_SIZE_ARB, i));
+        [x, y, z, invocations as GLuint]
+    });
+    ([x, y, z], invocations)
+}
+
+///
+///The number of work groups of `local_size` needed to run at least the given number of threads
+///in each dimension
+///
+///This fails if any dimension of `local_size` is zero or if more groups are needed than the
+///driver can dispatch
+///
+pub fn work_group_count(threads: [GLuint; 3], local_size: [GLuint; 3]) -> Result<[GLuint; 3], GLError> {
+    if local_size.contains(&0) {
+        return Err(GLError::InvalidOperation(format!("Invalid local size: {:?}", local_size)));
+    }
+
+    let mut groups = [0; 3];
+    for i in 0..3 {
+        groups[i] = threads[i] / local_size[i] + (threads[i] % local_size[i] != 0) as GLuint;
+    }
+
+    let max = max_work_group_count();
+    if (0..3).any(|i| groups[i] > max[i]) {
+        return Err(GLError::InvalidOperation(format!(
+            "Too many work groups for {:?} threads: {:?} > {:?}", threads, groups, max
+        )));
+    }
+    Ok(groups)
+}
+
+///
+///Dispatches the currently used compute program with the given number of work groups, each of
+///which have the given local size
+///
+///Requires `ARB_compute_variable_group_size`, and fails without dispatching anything if
+///`glDispatchComputeGroupSizeARB` isn't loaded or if `local_size` is beyond the driver's limits
+///
+///# Unsafety
+///
+///The caller must make sure a compute program with `layout(local_size_variable) in;` is in use and
+///that all of its inputs are bound
+///
+pub unsafe fn dispatch_group_size(groups: [GLuint; 3], local_size: [GLuint; 3]) -> Result<(), GLError> {
+    let dispatch = dispatch_compute_group_size()?;
+
+    let (max, max_invocations) = max_variable_group_size();
+    if (0..3).any(|i| local_size[i] > max[i]) ||
+        local_size.iter().map(|&s| s as u64).product::<u64>() > max_invocations as u64
+    {
+        return Err(GLError::InvalidOperation(format!(
+            "Local size {:?} is beyond the limits of {:?} and {} invocations", local_size, max, max_invocations
+        )));
+    }
+
+    dispatch(groups[0], groups[1], groups[2], local_size[0], local_size[1], local_size[2]);
+    Ok(())
+}
+
+///
+///A set of the kinds of memory accesses that need to see the writes of previous shaders, as
+///passed to `glMemoryBarrier`
+///
+///Barriers can be combined with `|`, such that `MemoryBarrier::SHADER_STORAGE | MemoryBarrier::COMMAND`
+///makes both the shader storage and the indirect command reads of following calls see any
+///incoherent writes that came before.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct MemoryBarrier(GLbitfield);
+
+impl MemoryBarrier {
+    pub const NONE: Self = MemoryBarrier(0);
+    pub const VERTEX_ATTRIB_ARRAY: Self = MemoryBarrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+    pub const ELEMENT_ARRAY: Self = MemoryBarrier(gl::ELEMENT_ARRAY_BARRIER_BIT);
+    pub const UNIFORM: Self = MemoryBarrier(gl::UNIFORM_BARRIER_BIT);
+    pub const TEXTURE_FETCH: Self = MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT);
+    pub const SHADER_IMAGE_ACCESS: Self = MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+    pub const COMMAND: Self = MemoryBarrier(gl::COMMAND_BARRIER_BIT);
+    pub const PIXEL_BUFFER: Self = MemoryBarrier(gl::PIXEL_BUFFER_BARRIER_BIT);
+    pub const TEXTURE_UPDATE: Self = MemoryBarrier(gl::TEXTURE_UPDATE_BARRIER_BIT);
+    pub const BUFFER_UPDATE: Self = MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+    pub const CLIENT_MAPPED_BUFFER: Self = MemoryBarrier(gl::CLIENT_MAPPED_BUFFER_BARRIER_BIT);
+    pub const QUERY_BUFFER: Self = MemoryBarrier(gl::QUERY_BUFFER_BARRIER_BIT);
+    pub const FRAMEBUFFER: Self = MemoryBarrier(gl::FRAMEBUFFER_BARRIER_BIT);
+    pub const TRANSFORM_FEEDBACK: Self = MemoryBarrier(gl::TRANSFORM_FEEDBACK_BARRIER_BIT);
+    pub const ATOMIC_COUNTER: Self = MemoryBarrier(gl::ATOMIC_COUNTER_BARRIER_BIT);
+    pub const SHADER_STORAGE: Self = MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+    pub const ALL: Self = MemoryBarrier(gl::ALL_BARRIER_BITS);
+
+    //the only barriers that can be used with glMemoryBarrierByRegion
+    const BY_REGION: Self = MemoryBarrier(
+        gl::ATOMIC_COUNTER_BARRIER_BIT | gl::FRAMEBUFFER_BARRIER_BIT | gl::SHADER_IMAGE_ACCESS_BARRIER_BIT |
+        gl::SHADER_STORAGE_BARRIER_BIT | gl::TEXTURE_FETCH_BARRIER_BIT | gl::UNIFORM_BARRIER_BIT
+    );
+
+    #[inline] pub fn bits(self) -> GLbitfield { self.0 }
+    #[inline] pub fn is_empty(self) -> bool { self.0 == 0 }
+    #[inline] pub fn contains(self, other: Self) -> bool { self.0 & other.0 == other.0 }
+
+    ///
+    ///Issues this barrier on its own, as long as it isn't empty
+    ///
+    ///# Unsafety
+    ///
+    ///Like most raw gl calls, this requires a current context, which is normally guaranteed by
+    ///borrowing the [Context]
+    ///
+    pub unsafe fn issue(self) {
+        if !self.is_empty() { gl::MemoryBarrier(self.0); }
+    }
+}
+
+impl ::std::ops::BitOr for MemoryBarrier {
+    type Output = Self;
+    #[inline] fn bitor(self, rhs: Self) -> Self { MemoryBarrier(self.0 | rhs.0) }
+}
+
+impl ::std::ops::BitOrAssign for MemoryBarrier {
+    #[inline] fn bitor_assign(&mut self, rhs: Self) { self.0 |= rhs.0 }
+}
+
+impl ::std::ops::BitAnd for MemoryBarrier {
+    type Output = Self;
+    #[inline] fn bitand(self, rhs: Self) -> Self { MemoryBarrier(self.0 & rhs.0) }
+}
+
+impl ::std::ops::BitAndAssign for MemoryBarrier {
+    #[inline] fn bitand_assign(&mut self, rhs: Self) { self.0 &= rhs.0 }
+}
+
+impl Context {
+
+    ///
+    ///Makes the given kinds of accesses of any following gl calls see the incoherent writes of the
+    ///shaders that came before, like image stores and shader storage block writes
+    ///
+    pub fn memory_barrier(&mut self, barriers: MemoryBarrier) {
+        unsafe { barriers.issue(); }
+    }
+
+    ///
+    ///The same as [memory_barrier](Self::memory_barrier), but only for the accesses of fragment
+    ///shaders to the writes made for the same framebuffer region
+    ///
+    ///Only the atomic counter, framebuffer, shader image access, shader storage, texture fetch, and
+    ///uniform barriers are allowed (or [MemoryBarrier::ALL]).
+    ///
+    ///Requires GL 4.5 or `ARB_ES3_1_compatibility`
+    ///
+    pub fn memory_barrier_by_region(&mut self, barriers: MemoryBarrier) -> Result<(), GLError> {
+        check_loaded!(MemoryBarrierByRegion; ())?;
+
+        if barriers != MemoryBarrier::ALL && !MemoryBarrier::BY_REGION.contains(barriers) {
+            return Err(GLError::InvalidBits(barriers.0 & !MemoryBarrier::BY_REGION.0, "glMemoryBarrierByRegion".to_owned()));
+        }
+
+        unsafe { if !barriers.is_empty() { gl::MemoryBarrierByRegion(barriers.0); } }
+        Ok(())
+    }
+
+}
@@ -0,0 +1,320 @@
+use super::*;
+
+use std::collections::HashMap;
+
+use reloadable::{NamedUniform, UniformEntry};
+
+///
+///A buffer with its type erased so that buffers of different types can be bound to blocks by name
+///
+pub trait AnyBuffer {
+    fn data_size(&self) -> usize;
+    unsafe fn bind_indexed(&self, target: IndexedBufferTarget, binding: GLuint);
+}
+
+impl<T:?Sized, A:BufferAccess> AnyBuffer for Buffer<T, A> {
+    #[inline] fn data_size(&self) -> usize { Buffer::data_size(self) }
+    #[inline] unsafe fn bind_indexed(&self, target: IndexedBufferTarget, binding: GLuint) { target.bind_buffer(self, binding) }
+}
+
+///
+///An attribute with its type erased so that attributes of different types can be passed by name
+///
+pub trait AnyAttribute {
+    fn data_type(&self) -> Option<(DataType, usize)>;
+    fn vertex_count(&self) -> Option<usize>;
+    unsafe fn load(&self, location: &AttributeLocation);
+}
+
+impl<'a, A:GLSLType> AnyAttribute for Attribute<'a, A> {
+    #[inline] fn data_type(&self) -> Option<(DataType, usize)> { A::data_type() }
+    #[inline] fn vertex_count(&self) -> Option<usize> { Attribute::vertex_count(self) }
+    #[inline] unsafe fn load(&self, location: &AttributeLocation) { location.load(self) }
+}
+
+fn type_name(ty: Option<DataType>) -> String {
+    ty.map_or("unknown type".to_owned(), |ty| ty.to_string())
+}
+
+pub(crate) fn not_found(kind: &str, name: &str) -> GLError {
+    GLError::InvalidOperation(format!("No active {} named \"{}\"", kind, name))
+}
+
+//checks a rust type against a reflected variable, starting at the given array element
+pub(crate) fn check_type(kind: &str, name: &str, found: (Option<DataType>, GLint), expected: (DataType, usize), index: usize) -> Result<(), GLError> {
+    let (ty, array_size) = found;
+    let (expected, len) = expected;
+
+    //samplers and images are set to the index of the unit they read from
+    let unit = expected == DataType::Int && ty.is_some_and(DataType::is_opaque);
+    if ty != Some(expected) && !unit {
+        return Err(GLError::InvalidOperation(
+            format!("The {} \"{}\" is a {} but was given a {}", kind, name, type_name(ty), expected)
+        ));
+    }
+    if index + len > array_size.max(1) as usize {
+        return Err(GLError::InvalidOperation(format!(
+            "The {} \"{}\" has {} elements but was given {} starting at element {}", kind, name, array_size, len, index
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) type BlockBinding<'b> = (IndexedBufferTarget, GLuint, &'b dyn AnyBuffer);
+
+//finds the binding of a block and checks that the buffer is big enough for it
+pub(crate) fn check_block(
+    interface: &ProgramInterface, name: &str, buffer: &dyn AnyBuffer
+) -> Result<Option<(IndexedBufferTarget, GLuint)>, GLError> {
+    let found = interface.uniform_block(name).map(|b| (IndexedBufferTarget::UniformBuffer, b)).or_else(
+        || interface.storage_block(name).map(|b| (IndexedBufferTarget::ShaderStorageBuffer, b))
+    );
+
+    match found {
+        Some((target, block)) => {
+            if buffer.data_size() < block.data_size {
+                return Err(GLError::InvalidOperation(format!(
+                    "The block \"{}\" needs at least {} bytes but was given a buffer of {}", name, block.data_size, buffer.data_size()
+                )));
+            }
+            Ok(Some((target, block.binding)))
+        },
+        None => Ok(None)
+    }
+}
+
+pub(crate) unsafe fn bind_blocks(blocks: &[BlockBinding]) {
+    for (target, binding, buffer) in blocks { buffer.bind_indexed(*target, *binding); }
+}
+
+pub(crate) unsafe fn unbind_blocks(blocks: &[BlockBinding]) {
+    for (target, binding, _) in blocks { target.unbind(*binding); }
+}
+
+//finds the location of each attribute, checking its type and that it has enough vertices
+pub(crate) fn check_attributes<'b>(
+    interface: &ProgramInterface, attributes: &HashMap<&str, &'b dyn AnyAttribute>, count: usize
+) -> Result<Vec<(AttributeLocation, &'b dyn AnyAttribute)>, GLError> {
+    attributes.iter().map(|(name, &attr)| {
+        let info = interface.attribute(name).or_else(
+            || interface.attribute(&format!("{}[0]", name))
+        ).ok_or_else(|| not_found("attribute", name))?;
+
+        if let Some(expected) = attr.data_type() {
+            check_type("attribute", name, (info.ty, info.array_size), expected, 0)?;
+        }
+        if let Some(len) = attr.vertex_count() {
+            if count > len {
+                return Err(GLError::InvalidOperation(
+                    format!("The attribute \"{}\" has {} vertices but {} were drawn", name, len, count)
+                ));
+            }
+        }
+
+        Ok((AttributeLocation::from_raw(info.location), attr))
+    }).collect()
+}
+
+///
+///A program built from shaders that are only known at runtime
+///
+///Since none of its interface is known at compile time, everything is looked up by name in the
+///program's [reflection](ProgramID::reflect), and is checked against it so that passing the wrong type of
+///value returns an error instead of silently generating a GL error. Types that aren't reflected as
+///a single variable (like structs from [glsl!](crate::glsl)) can only be checked for whether they exist.
+///
+///Like the programs from [glsl!](crate::glsl), each block is given its own binding index and uniforms
+///are only sent to the GPU when they change.
+///
+///Requires GL 4.3 or `ARB_program_interface_query`
+///
+pub struct DynamicProgram {
+    program: ProgramID,
+    interface: ProgramInterface,
+    uniforms: HashMap<String, Box<dyn NamedUniform>>,
+    attribute: Option<RawVertexArray>
+}
+
+impl DynamicProgram {
+
+    pub fn new(gl: &GLProvider, shaders: Vec<(&str, ShaderType)>) -> Result<Self, GLError> {
+        Ok(Self::from_program(gl, ProgramID::from_source(gl, shaders)?))
+    }
+
+    pub fn from_program(gl: &GLProvider, program: ProgramID) -> Self {
+        let mut interface = program.reflect();
+
+        unsafe {
+            for (i, block) in interface.uniform_blocks.iter_mut().enumerate() {
+                block.binding = i as GLuint;
+                gl::UniformBlockBinding(program.id(), i as GLuint, block.binding);
+            }
+            for (i, block) in interface.storage_blocks.iter_mut().enumerate() {
+                block.binding = i as GLuint;
+                gl::ShaderStorageBlockBinding(program.id(), i as GLuint, block.binding);
+            }
+        }
+
+        DynamicProgram {
+            program: program,
+            interface: interface,
+            uniforms: HashMap::new(),
+            attribute: if gl::GenVertexArrays::is_loaded() { Some(RawVertexArray::gen(gl)) } else { None }
+        }
+    }
+
+    #[inline] pub fn program(&self) -> &ProgramID { &self.program }
+    #[inline] pub fn interface(&self) -> &ProgramInterface { &self.interface }
+
+    //finds a uniform along with the array element being referred to
+    fn find_uniform(&self, name: &str) -> Option<(&UniformInfo, usize)> {
+        if let Some(u) = self.interface.uniform(name) { return Some((u, 0)); }
+        if let Some(u) = self.interface.uniform(&format!("{}[0]", name)) { return Some((u, 0)); }
+
+        //the elements of an array are all listed under the first one
+        let (base, index) = name.strip_suffix(']')?.rsplit_once('[')?;
+        let index = index.parse().ok()?;
+        self.interface.uniform(&format!("{}[0]", base)).map(|u| (u, index))
+    }
+
+    fn uniform_location<T:GLSLType>(&self, name: &str) -> Result<UniformLocation, GLError> {
+        //there's no draw here that knows to bind the textures of a sampler value
+        if T::texture_units() > 0 {
+            return Err(GLError::InvalidOperation(format!(
+                "The uniform \"{}\" holds samplers, so its texture unit has to be set as an int instead", name
+            )));
+        }
+
+        let location_name = match T::data_type() {
+            Some(expected) => {
+                let (info, index) = self.find_uniform(name).ok_or_else(|| not_found("uniform", name))?;
+                if info.location < 0 {
+                    return Err(GLError::InvalidOperation(
+                        format!("The uniform \"{}\" is in a uniform block and has to be set through a buffer", name)
+                    ));
+                }
+                check_type("uniform", name, (info.ty, info.array_size), expected, index)?;
+                match info.name.strip_suffix("[0]") {
+                    Some(base) => format!("{}[{}]", base, index),
+                    None => info.name.clone()
+                }
+            },
+            None => T::first_element_name(name.to_owned())
+        };
+
+        UniformLocation::get(&self.program, &location_name).map_err(|_| not_found("uniform", name))
+    }
+
+    fn uniform_entry<T:GLSLType+'static>(&mut self, name: &str) -> Result<&mut Uniform<T>, GLError> {
+        let location = self.uniform_location::<T>(name)?;
+
+        let cached = self.uniforms.get_mut(name).map_or(false, |u| u.as_any_mut().is::<UniformEntry<T>>());
+        if !cached {
+            let value = unsafe { location.get_uniform::<T>() };
+            self.uniforms.insert(name.to_owned(), Box::new(UniformEntry { location, value }));
+        }
+
+        let entry = self.uniforms.get_mut(name).unwrap().as_any_mut().downcast_mut::<UniformEntry<T>>().unwrap();
+        Ok(&mut entry.value)
+    }
+
+    ///
+    ///Sets the value of the uniform with the given name, which gets loaded on the next draw or compute
+    ///
+    ///Individual array elements can be set by indexing the name, like `lights[2]`.
+    ///
+    pub fn set_uniform<T:GLSLType+'static>(&mut self, name: &str, value: T) -> Result<(), GLError> {
+        **self.uniform_entry::<T>(name)? = value;
+        Ok(())
+    }
+
+    ///Gets the value of the uniform with the given name, including any changes that haven't been loaded yet
+    pub fn get_uniform<T:GLSLType+'static>(&self, name: &str) -> Result<T, GLError> {
+        let location = self.uniform_location::<T>(name)?;
+        match self.uniforms.get(name).and_then(|u| u.as_any().downcast_ref::<UniformEntry<T>>()) {
+            Some(entry) => Ok(*entry.value),
+            None => Ok(unsafe { *location.get_uniform::<T>() })
+        }
+    }
+
+    ///Binds a buffer to the block with the given name, checking that it's large enough to hold the block
+    pub fn bind_block<T:?Sized, A:BufferAccess>(&self, name: &str, buffer: &Buffer<T, A>) -> Result<(), GLError> {
+        let (target, binding) = check_block(&self.interface, name, buffer)?.ok_or_else(|| not_found("block", name))?;
+        unsafe { buffer.bind_indexed(target, binding); }
+        Ok(())
+    }
+
+    fn check_blocks<'b>(&self, blocks: &HashMap<&str, &'b dyn AnyBuffer>) -> Result<Vec<BlockBinding<'b>>, GLError> {
+        blocks.iter().map(|(name, &buffer)| {
+            let (target, binding) = check_block(&self.interface, name, buffer)?.ok_or_else(|| not_found("block", name))?;
+            Ok((target, binding, buffer))
+        }).collect()
+    }
+
+    unsafe fn bind_blocks(&self, blocks: &[BlockBinding]) {
+        //make sure the uniforms are loaded onto the gpu
+        self.program.use_program();
+        self.load_uniforms();
+        bind_blocks(blocks);
+    }
+
+    #[inline]
+    pub(crate) unsafe fn load_uniforms(&self) {
+        for uniform in self.uniforms.values() { uniform.load(); }
+    }
+
+    unsafe fn unbind_blocks(&self, blocks: &[BlockBinding]) {
+        unbind_blocks(blocks);
+        ProgramID::unbind_program();
+    }
+
+    ///
+    ///Draws `count` vertices with the given attributes and blocks, each keyed by their name in the shader
+    ///
+    ///Everything is checked before anything gets bound, so on an error, nothing is drawn.
+    ///
+    pub fn draw(
+        &self,
+        _context: &mut Context,
+        mode: DrawMode,
+        count: usize,
+        attributes: &HashMap<&str, &dyn AnyAttribute>,
+        blocks: &HashMap<&str, &dyn AnyBuffer>
+    ) -> Result<(), GLError> {
+        let mode = mode.with_patch_vertices(self.program.patch_vertices());
+        mode.validate_stages(self.program.stages().contains(&ShaderType::TessEval), self.program.geometry_input())?;
+        let attributes = check_attributes(&self.interface, attributes, count)?;
+        let blocks = self.check_blocks(blocks)?;
+
+        unsafe {
+            self.bind_blocks(&blocks);
+            if let Some(vao) = &self.attribute { vao.bind(); }
+            for (location, attr) in attributes.iter() { attr.load(location); }
+
+            mode.set_patch_vertices();
+            gl::DrawArrays(GLenum::from(mode), 0, count as GLsizei);
+
+            RawVertexArray::unbind();
+            self.unbind_blocks(&blocks);
+        }
+        Ok(())
+    }
+
+    ///Dispatches the given number of work groups with the given blocks, each keyed by their name in the shader
+    pub fn compute(
+        &self, count_x: GLuint, count_y: GLuint, count_z: GLuint, blocks: &HashMap<&str, &dyn AnyBuffer>
+    ) -> Result<(), GLError> {
+        if self.interface.work_group_size.is_none() {
+            return Err(GLError::InvalidOperation("Cannot dispatch a program without a compute shader".to_owned()));
+        }
+        let blocks = self.check_blocks(blocks)?;
+
+        unsafe {
+            self.bind_blocks(&blocks);
+            gl::DispatchCompute(count_x, count_y, count_z);
+            self.unbind_blocks(&blocks);
+        }
+        Ok(())
+    }
+
+}
@@ -13,6 +13,11 @@ unsafe impl AttribFormat for IntFormat {
         gl::VertexAttribIPointer(attr_id, 1, self.into(), stride as GLsizei, transmute(offset as GLintptr));
     }
 
+    #[inline]
+    unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize){
+        gl::VertexAttribIFormat(attr_id, 1, self.into(), relative_offset as GLuint);
+    }
+
     #[inline]
     unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid){
         FloatFormat::FromInt(self, false).set_attribute(attr_id, data);
@@ -62,6 +67,11 @@ unsafe impl AttribFormat for FloatFormat {
         gl::VertexAttribPointer(attr_id, 1, self.gl_type(), self.normalized() as GLboolean, stride as GLsizei, transmute(offset as GLintptr));
     }
 
+    #[inline]
+    unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize){
+        gl::VertexAttribFormat(attr_id, 1, self.gl_type(), self.normalized() as GLboolean, relative_offset as GLuint);
+    }
+
     #[inline]
     unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid){
         VecFormat::VecN(self, 1).set_attribute(attr_id, data);
@@ -79,6 +89,11 @@ unsafe impl AttribFormat for DoubleFormat {
         gl::VertexAttribLPointer(attr_id, 1, gl::DOUBLE, stride as GLsizei, transmute(offset as GLintptr));
     }
 
+    #[inline]
+    unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize){
+        gl::VertexAttribLFormat(attr_id, 1, gl::DOUBLE, relative_offset as GLuint);
+    }
+
     #[inline]
     unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid){
         DVecFormat::DVecN(1).set_attribute(attr_id, data);
@@ -139,19 +154,30 @@ unsafe impl AttribFormat for VecFormat {
         gl::VertexAttribPointer(attr_id, 4.min(self.elements() as GLint), self.gl_type(), self.normalized() as GLboolean, stride as GLsizei, transmute(offset as GLintptr));
     }
 
+    #[inline]
+    unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize){
+        gl::VertexAttribFormat(attr_id, 4.min(self.elements() as GLint), self.gl_type(), self.normalized() as GLboolean, relative_offset as GLuint);
+    }
+
     #[inline]
     unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid){
         match self {
             VecFormat::VecN(f, c) => {
                 if c==0 {panic!("Zero size vecs are invalid");}
                 match f {
-                    FloatFormat::Float(FloatType::Half) => {
-                        match c {
-                            1 => gl::VertexAttrib1sv(attr_id, transmute(data)),
-                            2 => gl::VertexAttrib2sv(attr_id, transmute(data)),
-                            3 => gl::VertexAttrib3sv(attr_id, transmute(data)),
-                            _ => gl::VertexAttrib4sv(attr_id, transmute(data)),
+                    //there are no glVertexAttrib* functions for half-floats or fixed-point,
+                    //so we have to convert them to floats first
+                    FloatFormat::Float(FloatType::Half) | FloatFormat::Fixed => {
+                        let mut arr = [0.0f32, 0.0, 0.0, 1.0];
+                        for i in 0..c.min(4) {
+                            arr[i] = match f {
+                                FloatFormat::Fixed => (*(data as *const glsl_type::fixed).add(i)).into(),
+                                _ => (*(data as *const glsl_type::f16).add(i)).into()
+                            };
                         }
+                        VecFormat::VecN(FloatFormat::Float(FloatType::Float), c).set_attribute(
+                            attr_id, &arr[0] as *const GLfloat as *const GLvoid
+                        );
                     },
                     FloatFormat::Float(FloatType::Float) => {
                         match c {
@@ -169,7 +195,6 @@ unsafe impl AttribFormat for VecFormat {
                             _ => gl::VertexAttrib4dv(attr_id, transmute(data)),
                         }
                     },
-                    FloatFormat::Fixed => unimplemented!(),
                     FloatFormat::FromInt(z, normalized) => {
                         unsafe fn to_vec4<G:Copy>(ptr: *const GLvoid, count:usize, zero: G, one: G) -> [G;4] {
                             let p: *const G = transmute(ptr);
@@ -248,6 +273,11 @@ unsafe impl AttribFormat for VecFormat {
                     }
                 }
             },
+            //glVertexAttribP* only takes the 2_10_10_10 formats, so these get unpacked to floats instead
+            VecFormat::UInt10F_11F_11FRev(_) => {
+                let [x, y, z] = (*(data as *const glsl_type::Packed111110F)).unpack();
+                gl::VertexAttrib3f(attr_id, x, y, z);
+            },
             _ => gl::VertexAttribP4uiv(attr_id, self.gl_type(), self.normalized() as GLboolean, transmute(data))
         }
     }
@@ -273,6 +303,11 @@ unsafe impl AttribFormat for IVecFormat {
         gl::VertexAttribIPointer(attr_id, 4.min(self.elements() as GLint), self.gl_type(), stride as GLsizei, transmute(offset as GLintptr));
     }
 
+    #[inline]
+    unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize){
+        gl::VertexAttribIFormat(attr_id, 4.min(self.elements() as GLint), self.gl_type(), relative_offset as GLuint);
+    }
+
     #[inline]
     unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid){
         VecFormat::VecN(FloatFormat::FromInt(self.int_format(), false), self.elements()).set_attribute(attr_id, data);
@@ -297,6 +332,11 @@ unsafe impl AttribFormat for DVecFormat {
         gl::VertexAttribLPointer(attr_id, 4.min(self.elements() as GLint), gl::DOUBLE, stride as GLsizei, transmute(offset as GLintptr));
     }
 
+    #[inline]
+    unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize){
+        gl::VertexAttribLFormat(attr_id, 4.min(self.elements() as GLint), gl::DOUBLE, relative_offset as GLuint);
+    }
+
     #[inline]
     unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid){
         match self {
@@ -322,6 +362,7 @@ unsafe impl AttribFormat for UnsupportedFormat {
     #[inline] fn size(self) -> usize { unimplemented!() }
     #[inline] fn attrib_count(self) -> usize { unimplemented!() }
     #[inline] unsafe fn bind_attribute(self, _attr_id: GLuint, _stride: usize, _offset: usize){ unimplemented!() }
+    #[inline] unsafe fn attribute_format(self, _attr_id: GLuint, _relative_offset: usize){ unimplemented!() }
     #[inline] unsafe fn set_attribute(self, _attr_id: GLuint, _data: *const GLvoid){ unimplemented!() }
 }
 
@@ -347,6 +388,13 @@ macro_rules! array_format {
                     }
                 }
 
+                #[inline]
+                unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize) {
+                    for i in 0..$num {
+                        self[i].attribute_format(attr_id + (i as GLuint)*(self[i].attrib_count() as GLuint), relative_offset + i*self[i].size());
+                    }
+                }
+
                 #[inline]
                 unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid){
                     for i in 0..$num {
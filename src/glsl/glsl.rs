@@ -20,6 +20,42 @@ macro_rules! glsl {
     //the only exception is bool since that type name is a rust primitive as well
     ([bool] @ty $($code:tt)*) => {glsl!([c_bool] $($code)*);};
 
+    //samplers borrow their textures for the lifetime of the Program they're set in
+    ([sampler1D]              @ty $($code:tt)*) => {glsl!([sampler1D<'a>] $($code)*);};
+    ([sampler2D]              @ty $($code:tt)*) => {glsl!([sampler2D<'a>] $($code)*);};
+    ([sampler3D]              @ty $($code:tt)*) => {glsl!([sampler3D<'a>] $($code)*);};
+    ([samplerCube]            @ty $($code:tt)*) => {glsl!([samplerCube<'a>] $($code)*);};
+    ([sampler2DRect]          @ty $($code:tt)*) => {glsl!([sampler2DRect<'a>] $($code)*);};
+    ([sampler1DArray]         @ty $($code:tt)*) => {glsl!([sampler1DArray<'a>] $($code)*);};
+    ([sampler2DArray]         @ty $($code:tt)*) => {glsl!([sampler2DArray<'a>] $($code)*);};
+    ([samplerCubeArray]       @ty $($code:tt)*) => {glsl!([samplerCubeArray<'a>] $($code)*);};
+    ([samplerBuffer]          @ty $($code:tt)*) => {glsl!([samplerBuffer<'a>] $($code)*);};
+    ([isampler1D]             @ty $($code:tt)*) => {glsl!([isampler1D<'a>] $($code)*);};
+    ([isampler2D]             @ty $($code:tt)*) => {glsl!([isampler2D<'a>] $($code)*);};
+    ([isampler3D]             @ty $($code:tt)*) => {glsl!([isampler3D<'a>] $($code)*);};
+    ([isamplerCube]           @ty $($code:tt)*) => {glsl!([isamplerCube<'a>] $($code)*);};
+    ([isampler2DRect]         @ty $($code:tt)*) => {glsl!([isampler2DRect<'a>] $($code)*);};
+    ([isampler1DArray]        @ty $($code:tt)*) => {glsl!([isampler1DArray<'a>] $($code)*);};
+    ([isampler2DArray]        @ty $($code:tt)*) => {glsl!([isampler2DArray<'a>] $($code)*);};
+    ([isamplerCubeArray]      @ty $($code:tt)*) => {glsl!([isamplerCubeArray<'a>] $($code)*);};
+    ([isamplerBuffer]         @ty $($code:tt)*) => {glsl!([isamplerBuffer<'a>] $($code)*);};
+    ([usampler1D]             @ty $($code:tt)*) => {glsl!([usampler1D<'a>] $($code)*);};
+    ([usampler2D]             @ty $($code:tt)*) => {glsl!([usampler2D<'a>] $($code)*);};
+    ([usampler3D]             @ty $($code:tt)*) => {glsl!([usampler3D<'a>] $($code)*);};
+    ([usamplerCube]           @ty $($code:tt)*) => {glsl!([usamplerCube<'a>] $($code)*);};
+    ([usampler2DRect]         @ty $($code:tt)*) => {glsl!([usampler2DRect<'a>] $($code)*);};
+    ([usampler1DArray]        @ty $($code:tt)*) => {glsl!([usampler1DArray<'a>] $($code)*);};
+    ([usampler2DArray]        @ty $($code:tt)*) => {glsl!([usampler2DArray<'a>] $($code)*);};
+    ([usamplerCubeArray]      @ty $($code:tt)*) => {glsl!([usamplerCubeArray<'a>] $($code)*);};
+    ([usamplerBuffer]         @ty $($code:tt)*) => {glsl!([usamplerBuffer<'a>] $($code)*);};
+    ([sampler1DShadow]        @ty $($code:tt)*) => {glsl!([sampler1DShadow<'a>] $($code)*);};
+    ([sampler2DShadow]        @ty $($code:tt)*) => {glsl!([sampler2DShadow<'a>] $($code)*);};
+    ([samplerCubeShadow]      @ty $($code:tt)*) => {glsl!([samplerCubeShadow<'a>] $($code)*);};
+    ([sampler2DRectShadow]    @ty $($code:tt)*) => {glsl!([sampler2DRectShadow<'a>] $($code)*);};
+    ([sampler1DArrayShadow]   @ty $($code:tt)*) => {glsl!([sampler1DArrayShadow<'a>] $($code)*);};
+    ([sampler2DArrayShadow]   @ty $($code:tt)*) => {glsl!([sampler2DArrayShadow<'a>] $($code)*);};
+    ([samplerCubeArrayShadow] @ty $($code:tt)*) => {glsl!([samplerCubeArrayShadow<'a>] $($code)*);};
+
     //this captures all constructed types and primitives (besides bool)
     ([$name:ident] @ty $($code:tt)*) => {glsl!([$name] $($code)*);};
 
@@ -83,6 +119,14 @@ macro_rules! glsl {
     ({$($rust:tt)*} {readonly $($fields:tt)*} @decl_list $($code:tt)*) => { glsl!({$($rust)*} {$($fields)*} @decl_list $($code)*);};
     ({$($rust:tt)*} {writeonly $($fields:tt)*} @decl_list $($code:tt)*) => { glsl!({$($rust)*} {$($fields)*} @decl_list $($code)*);};
 
+    //normalized integer fields, which are stored as Norm or SNorm on the rust side but read as floats
+    ({$($rust:tt)*} {@Norm($prim:ident) $type:ident $name:ident; $($fields:tt)*} @decl_list $($code:tt)*) => {
+        glsl!({$($rust)* [$name: glsl!(@norm Norm $prim $type) => $type]} {$($fields)*} @decl_list $($code)*);
+    };
+    ({$($rust:tt)*} {@SNorm($prim:ident) $type:ident $name:ident; $($fields:tt)*} @decl_list $($code:tt)*) => {
+        glsl!({$($rust)* [$name: glsl!(@norm SNorm $prim $type) => $type]} {$($fields)*} @decl_list $($code)*);
+    };
+
     //parse out the list of types from a single declaration
     ({$($rust:tt)*} {} @decl_list $($code:tt)*) => { glsl!({$($rust)*} $($code)*); };
     ({$($rust:tt)*} {$type:ident $($names:ident $([$($s:tt)*])*),*; $($fields:tt)*} @decl_list $($code:tt)*) => {
@@ -103,7 +147,7 @@ macro_rules! glsl {
     //A subroutine for getting unique generic identifiers
     //
 
-    //for attributes
+    //for captured outputs
     ([T0] @next_generic $($code:tt)*) => { glsl!([T1] $($code)*); };
     ([T1] @next_generic $($code:tt)*) => { glsl!([T2] $($code)*); };
     ([T2] @next_generic $($code:tt)*) => { glsl!([T3] $($code)*); };
@@ -119,7 +163,7 @@ macro_rules! glsl {
     ([Tc] @next_generic $($code:tt)*) => { glsl!([Td] $($code)*); };
     ([Td] @next_generic $($code:tt)*) => { glsl!([Te] $($code)*); };
     ([Te] @next_generic $($code:tt)*) => { glsl!([Tf] $($code)*); };
-    ([Tf] @next_generic $($code:tt)*) => { compile_error!("Too many vertex attributes! (Max=16)") };
+    ([Tf] @next_generic $($code:tt)*) => { compile_error!("Too many captured outputs! (Max=16)") };
 
     //for the access parameter for the buffers for interface blocks
     ([A0] @next_generic $($code:tt)*) => { glsl!([A1] $($code)*); };
@@ -148,6 +192,8 @@ macro_rules! glsl {
     //
 
     (@stringify ) => { "" };
+    (@stringify @Norm($($c:tt)*) $($rest:tt)*) => { glsl!(@stringify $($rest)*) };
+    (@stringify @SNorm($($c:tt)*) $($rest:tt)*) => { glsl!(@stringify $($rest)*) };
     (@stringify {$($lines:tt)*} $($rest:tt)*) => { concat!("{\n", glsl!(@stringify $($lines)*) ,"}", glsl!(@stringify $($rest)*)) };
     (@stringify ($($c:tt)*) $($rest:tt)*) => { concat!("(", glsl!(@stringify $($c)*) ,")", glsl!(@stringify $($rest)*)) };
     (@stringify [$($c:tt)*] $($rest:tt)*) => { concat!("[", glsl!(@stringify $($c)*) ,"]", glsl!(@stringify $($rest)*)) };
@@ -155,6 +201,58 @@ macro_rules! glsl {
     (@stringify ; $($rest:tt)*) => { concat!(";\n", glsl!(@stringify $($rest)*)) };
     (@stringify $c:tt $($rest:tt)*) => { concat!(stringify!($c), glsl!(@stringify $($rest)*)) };
 
+    //
+    //The rust-side storage of normalized struct fields, where each component gets wrapped
+    //
+
+    (@norm $N:ident $prim:ident float) => { $N<$prim> };
+    (@norm $N:ident $prim:ident vec2) => { [$N<$prim>; 2] };
+    (@norm $N:ident $prim:ident vec3) => { [$N<$prim>; 3] };
+    (@norm $N:ident $prim:ident vec4) => { [$N<$prim>; 4] };
+    (@norm $N:ident $prim:ident $ty:ident) => { compile_error!(concat!("Cannot normalize type: ", stringify!($ty))) };
+
+    //the glsl type of a struct field, given its rust type and the glsl type if it differs
+    (@field_ty [$ty:ty]) => { $ty };
+    (@field_ty [$ty:ty] [$gty:ty]) => { $gty };
+
+    //
+    //The rust-side enums for subroutine types, with a variant for each of their functions
+    //
+
+    (@subroutines {} $all:tt) => {};
+    (@subroutines {[$T:ident] $($rest:tt)*} $all:tt) => {
+        glsl!(@subroutine_enum $T {} $all);
+        glsl!(@subroutines {$($rest)*} $all);
+    };
+    (@subroutines {[$T:ident $f:ident] $($rest:tt)*} $all:tt) => { glsl!(@subroutines {$($rest)*} $all); };
+
+    //collect the functions for this type, using the name-checking macro of each entry's type
+    (@subroutine_enum $T:ident {$($f:ident)*} {[$U:ident] $($rest:tt)*}) => {
+        glsl!(@subroutine_enum $T {$($f)*} {$($rest)*});
+    };
+    (@subroutine_enum $T:ident {$($f:ident)*} {[$U:ident $g:ident] $($rest:tt)*}) => {
+        $U!($T glsl @if {
+            @subroutine_enum $T {$($f)* $g} {$($rest)*}
+        } @then {
+            @subroutine_enum $T {$($f)*} {$($rest)*}
+        } @else);
+    };
+    (@subroutine_enum $T:ident {$($f:ident)*} {}) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+        pub enum $T { $($f),* }
+
+        impl GLSLSubroutine for $T {
+            fn function_name(&self) -> &'static ::std::ffi::CStr {
+                match *self {
+                    $($T::$f => unsafe {
+                        ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(stringify!($f), "\0").as_bytes())
+                    }),*
+                }
+            }
+        }
+    };
+
     //
     //--------------------------------------------------------------------------------------------
     //Control Structures
@@ -200,6 +298,10 @@ macro_rules! glsl {
     ([] [$val:expr] @count $($code:tt)*) => {glsl!([$val] $($code)*);};
     ([$t0:tt $($t:tt)*] [$val:expr] @count $($code:tt)*) => {glsl!([$($t)*] [$val + 1] @count $($code)*);};
 
+    //checking for an empty list
+    ([] @empty $($code:tt)*) => {glsl!([true] $($code)*);};
+    ([$($t:tt)+] @empty $($code:tt)*) => {glsl!([false] $($code)*);};
+
     //logical operation
     ([false] [$b:tt] @and $($code:tt)*) => {glsl!([false] $($code)*);};
     ([$b:tt] [false] @and $($code:tt)*) => {glsl!([false] $($code)*);};
@@ -238,6 +340,12 @@ macro_rules! glsl {
     (uni         uni         @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
     (attr        attr        @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
     (block       block       @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
+    (xfb         xfb         @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
+    (loc         loc         @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
+    (sub         sub         @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
+    (subuni      subuni      @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
+    (img         img         @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
+    (local       local       @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
     (src         src         @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
     (flags       flags       @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
     (fun         fun         @bucket $($code:tt)*) => {glsl!([true]  $($code)*);};
@@ -368,6 +476,81 @@ macro_rules! glsl {
         glsl!(($d @block {$($blocks)* [$name<$kind, $layout, $A>: $ty]} $($data)*) $($code)*);
     };
 
+    //
+    //Outputs captured with transform feedback
+    //
+
+    //get the capture bucket
+    ($data:tt [$($ty:tt)*] $name:ident @capture $($code:tt)*) => {
+        glsl!($data xfb @bucket [$($ty)*] $name @capture $($code)*);
+    };
+
+    //get a generic for the captured data and the rust type of the output
+    (($d:tt $($data:tt)*) xfb {$($xfb:tt)*} [$($ty:tt)*] $name:ident @capture $($code:tt)*) => {
+        glsl! {
+            $name {$($xfb)*} @contains_name
+            @if {
+                ($d @xfb {$($xfb)*} $($data)*)
+            } @then {
+                [T0] {$($xfb)*} @generic
+                { [$($ty)*] @ty } @eval
+                ($d $($data)*) {$($xfb)*} $name @capture
+            } @else $($code)*
+        }
+    };
+
+    //register the captured output
+    ([$T:ident] [$ty:ty] ($d:tt $($data:tt)*) {$($xfb:tt)*} $name:ident @capture $($code:tt)*) => {
+        _register_name!($d $name);
+        glsl!(($d @xfb {$($xfb)* [$name<$T>: $ty]} $($data)*) $($code)*);
+    };
+
+    //
+    //Explicit locations of vertex inputs and fragment outputs
+    //
+
+    ($data:tt $dir:ident $name:ident [$n:literal] @location $($code:tt)*) => {
+        glsl!($data loc @bucket $dir $name [$n] @location $($code)*);
+    };
+    (($d:tt $($data:tt)*) loc {$($loc:tt)*} $dir:ident $name:ident [$n:literal] @location $($code:tt)*) => {
+        glsl!(($d @loc {$($loc)* [$dir $name = $n]} $($data)*) $($code)*);
+    };
+
+    //
+    //Subroutine types, the functions for them, and subroutine uniforms
+    //
+
+    //get the subroutine bucket
+    ($data:tt [$($new:tt)*] @subroutine $($code:tt)*) => {
+        glsl!($data sub @bucket [$($new)*] @subroutine $($code)*);
+    };
+
+    //a subroutine type, which only needs to be registered once even if every stage declares it
+    (($d:tt $($data:tt)*) sub {$($sub:tt)*} [$T:ident] @subroutine $($code:tt)*) => {
+        glsl! {
+            $T {$($sub)*} @contains_name
+            @if {
+                ($d @sub {$($sub)*} $($data)*)
+            } @then {
+                $d $T _register_name @call
+                ($d @sub {$($sub)* [$T]} $($data)*)
+            } @else $($code)*
+        }
+    };
+
+    //a function that can be used for a subroutine type
+    (($d:tt $($data:tt)*) sub {$($sub:tt)*} [$T:ident $f:ident] @subroutine $($code:tt)*) => {
+        glsl!(($d @sub {$($sub)* [$T $f]} $($data)*) $($code)*);
+    };
+
+    //subroutine uniforms are set separately for each stage, so we need to know which one we're in
+    ($data:tt [$S:ident] $name:ident @subroutine_uniform $($code:tt)*) => {
+        glsl!($data current @bucket [$S] $name @subroutine_uniform $($code)*);
+    };
+    ($data:tt current {$shdr:ident $src:tt} [$S:ident] $name:ident @subroutine_uniform $($code:tt)*) => {
+        glsl!($data current {$shdr $src} @push_bucket [$name: $S = $shdr] subuni @add_to @ignore $($code)*);
+    };
+
     //
     //Register a shader and store it's source code
     //
@@ -385,6 +568,10 @@ macro_rules! glsl {
     //
     //Set a flag
     //
+    //In order, the flags are: vertex, fragment, compute, separable, and tessellation evaluation,
+    //followed by the output patch size of a tessellation control shader and the input primitive of a
+    //geometry shader, both of which stay false unless declared
+    //
 
     //get the flag bucket
     ($data:tt [$val:tt] [$($id:tt)*] @set_flag $($code:tt)* ) => {
@@ -405,7 +592,21 @@ macro_rules! glsl {
 
     //start a new set of parsing data
     ($data:tt $shdr:ident @new_shdr $($code:tt)*) => {
-        glsl!($data current {$shdr {"".to_owned(); ""}} @set_bucket $($code)*);
+        glsl!($data current {$shdr {"".to_owned(); ""}} @set_bucket fun @bucket @new_deps $($code)*);
+    };
+
+    //clear the dependencies for the next public item (but keep track of what's already been imported)
+    ($data:tt fun {$deps:tt $imported:tt} @new_deps $($code:tt)*) => {
+        glsl!($data fun {{} $imported} @push_bucket $($code)*);
+    };
+
+    //check if the current shader is a fragment shader
+    ($data:tt @is_fragment $($code:tt)*) => { glsl!($data current @bucket @is_fragment $($code)*); };
+    ($data:tt current {Fragment $src:tt} @is_fragment $($code:tt)*) => {
+        glsl!($data current {Fragment $src} @push_bucket [true] $($code)*);
+    };
+    ($data:tt current {$shdr:ident $src:tt} @is_fragment $($code:tt)*) => {
+        glsl!($data current {$shdr $src} @push_bucket [false] $($code)*);
     };
 
     //check if the current shader is a vertex shader
@@ -417,12 +618,96 @@ macro_rules! glsl {
         glsl!($data current {$shdr $src} @push_bucket [false] $($code)*);
     };
 
+    //check if the current shader is a geometry shader
+    ($data:tt @is_geometry $($code:tt)*) => { glsl!($data current @bucket @is_geometry $($code)*); };
+    ($data:tt current {Geometry $src:tt} @is_geometry $($code:tt)*) => {
+        glsl!($data current {Geometry $src} @push_bucket [true] $($code)*);
+    };
+    ($data:tt current {$shdr:ident $src:tt} @is_geometry $($code:tt)*) => {
+        glsl!($data current {$shdr $src} @push_bucket [false] $($code)*);
+    };
+
+    //check if a type is one of the image types
+    ($data:tt [image1D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [image2D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [image3D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [imageCube] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [image2DRect] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [image1DArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [image2DArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [imageCubeArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [imageBuffer] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimage1D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimage2D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimage3D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimageCube] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimage2DRect] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimage1DArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimage2DArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimageCubeArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [iimageBuffer] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimage1D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimage2D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimage3D] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimageCube] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimage2DRect] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimage1DArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimage2DArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimageCubeArray] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [uimageBuffer] @is_image $($code:tt)*) => { glsl!($data [true] $($code)*); };
+    ($data:tt [$ty:ident] @is_image $($code:tt)*) => { glsl!($data [false] $($code)*); };
+
+    //check how the work group size of a compute shader was declared
+    ({[$x:tt] [$y:tt] [$z:tt]} @fixed_local_size $($code:tt)*) => { glsl!([true] $($code)*); };
+    ({$($local:tt)*} @fixed_local_size $($code:tt)*) => { glsl!([false] $($code)*); };
+    ({variable} @variable_local_size $($code:tt)*) => { glsl!([true] $($code)*); };
+    ({$($local:tt)*} @variable_local_size $($code:tt)*) => { glsl!([false] $($code)*); };
+
+    (@local_size_const [$x:tt] [$y:tt] [$z:tt]) => {
+        ///The work group size given by `layout(local_size_x = X, local_size_y = Y, local_size_z = Z) in;`
+        pub const LOCAL_SIZE: [GLuint; 3] = [$x, $y, $z];
+    };
+    (@local_size_const $($local:tt)*) => {};
+
+    //only the locations in the given direction go in each of the location modules
+    (@location_const input [input $name:ident = $n:literal]) => { pub const $name: GLuint = $n; };
+    (@location_const output [output $name:ident = $n:literal]) => { pub const $name: GLuint = $n; };
+    (@location_const $dir:ident $loc:tt) => {};
+
+    (@patch_vertices_const false) => {};
+    (@patch_vertices_const $n:tt) => {
+        ///
+        ///The number of vertices in the output patches, as given by `layout(vertices = N) out;`
+        ///
+        ///Drawing with `DrawMode::Patches(0)` uses this as the size of the input patches as well.
+        ///
+        pub const PATCH_VERTICES: GLuint = $n;
+    };
+    (@patch_vertices false) => { None };
+    (@patch_vertices $n:tt) => { Some($n) };
+
+    (@geometry_primitive false) => { None };
+    (@geometry_primitive points) => { Some(GeometryInput::Points) };
+    (@geometry_primitive lines) => { Some(GeometryInput::Lines) };
+    (@geometry_primitive lines_adjacency) => { Some(GeometryInput::LinesAdjacency) };
+    (@geometry_primitive triangles) => { Some(GeometryInput::Triangles) };
+    (@geometry_primitive triangles_adjacency) => { Some(GeometryInput::TrianglesAdjacency) };
+    (@geometry_primitive $prim:ident) => {
+        compile_error!(concat!("Invalid geometry shader input primitive: ", stringify!($prim)))
+    };
+
     //add a string literal to the string for the current shader's source code
     ($data:tt {$str:expr} @src_str $($code:tt)*) => { glsl!($data current @bucket {$str} @src_str $($code)*); };
     ($data:tt current {$shdr:ident {$first:expr; $last:expr}} {$str:expr} @src_str $($code:tt)*) => {
         glsl!($data current {$shdr {$first; concat!($last, $str)}} @push_bucket $($code)*);
     };
 
+    //pass the current shader's source code so far through a function
+    ($data:tt {$f:expr} @src_fn $($code:tt)*) => { glsl!($data current @bucket {$f} @src_fn $($code)*); };
+    ($data:tt current {$shdr:ident {$first:expr; $last:expr}} {$f:expr} @src_fn $($code:tt)*) => {
+        glsl!($data current {$shdr {$f($first+$last); ""}} @push_bucket $($code)*);
+    };
+
     //add a runtime expression to the string for the current shader's source code
     ($data:tt {$str:expr} @src_expr $($code:tt)*) => { glsl!($data current @bucket {$str} @src_expr $($code)*); };
     ($data:tt current {$shdr:ident {$first:expr; $last:expr}} {$str:expr} @src_expr $($code:tt)*) => {
@@ -463,11 +748,14 @@ macro_rules! glsl {
 
     ($data:tt @shader @Vertex $($code:tt)* ) => { glsl!($data [true] [] @set_flag Vertex @new_shdr @parse $($code)*); };
     ($data:tt @shader @TessControl $($code:tt)* ) => { glsl!($data TessControl @new_shdr @parse $($code)*); };
-    ($data:tt @shader @TessEval $($code:tt)* ) => { glsl!($data TessEval @new_shdr @parse $($code)*); };
+    ($data:tt @shader @TessEval $($code:tt)* ) => { glsl!($data [true] [,,,,] @set_flag TessEval @new_shdr @parse $($code)*); };
     ($data:tt @shader @Geometry $($code:tt)* ) => { glsl!($data Geometry @new_shdr @parse $($code)*); };
     ($data:tt @shader @Fragment $($code:tt)* ) => { glsl!($data [true] [,] @set_flag Fragment @new_shdr @parse $($code)*); };
     ($data:tt @shader @Compute $($code:tt)* ) => { glsl!($data [true] [,,] @set_flag Compute @new_shdr @parse $($code)*); };
     ($data:tt @shader @Lib $($code:tt)* ) => { glsl!($data Lib @new_shdr @parse $($code)*); };
+
+    //makes the program separable so its stages can be used in a ProgramPipeline
+    ($data:tt @shader @Separable $($code:tt)* ) => { glsl!($data [true] [,,,] @set_flag @shader $($code)*); };
     ($data:tt @shader @$err:ident $($code:tt)* ) => { compile_error!(concat!("Unrecognized GLSL shader type or macro directive: ", stringify!($err))); };
     ($data:tt @shader $err:tt $($code:tt)* ) => { compile_error!(concat!("Expected @[ShaderType], @Rust, or @Lib, found: ", stringify!($err))); };
 
@@ -505,8 +793,12 @@ macro_rules! glsl {
     ($data:tt @parse #ifndef $($code:tt)* ) => { compile_error!("#ifndef not supported (use Rust macros instead)");};
     ($data:tt @parse #if $($code:tt)* ) => { compile_error!("#if not supported (use Rust macros instead)");};
     ($data:tt @parse #elseif $($code:tt)* ) => { compile_error!("#elseif not supported (use Rust macros instead)");};
-    ($data:tt @parse #endif $($code:tt)* ) => { compile_error!("#endif not supported (use Rust macros instead)");};
-    ($data:tt @parse #include $($code:tt)* ) => { compile_error!("#include not supported (use Rust macros instead)");};
+    //includes are resolved at runtime from the ShaderFS shared by every GLProvider
+    //includes are resolved at runtime from the ShaderFS of the GLProvider the program is made with
+    ($data:tt @parse #include $name:literal $($code:tt)*) => {
+        glsl!($data {concat!("\n#include ", stringify!($name), "\n")} @src_str @parse $($code)*);
+    };
+    ($data:tt @parse #include $($code:tt)* ) => { compile_error!("#include requires a quoted file name");};
     ($data:tt @parse #pragma $($code:tt)* ) => { compile_error!("#pragma not supported (use Rust macros instead)");};
     ($data:tt @parse #line $($code:tt)* ) => { compile_error!("#line not supported (use Rust macros instead)");};
     ($data:tt @parse #$dir:ident $($code:tt)* ) => {
@@ -540,6 +832,62 @@ macro_rules! glsl {
         glsl!($data {<$name as GLSLFunction<$ret_type, $params>>::SRC} @src_expr @parse $($code)*);
     };
 
+    //importing public functions and structs from other glsl! modules
+    //(paths are relative to wherever the macro was invoked, just like the modules it creates)
+    ($data:tt @parse use crate $(::$m:ident)+; $($code:tt)*) => {
+        glsl!($data [crate] [$(::$m)+] @use @parse $($code)*);
+    };
+    ($data:tt @parse use $m0:ident $(::$m:ident)+; $($code:tt)*) => {
+        glsl!($data [super::$m0] [$(::$m)+] @use @parse $($code)*);
+    };
+
+    //split off the name of the item from the module path
+    ($data:tt [$($m:tt)*] [::$next:ident :: $($rest:tt)+] @use $($code:tt)*) => {
+        glsl!($data [$($m)*::$next] [::$($rest)+] @use $($code)*);
+    };
+
+    ($data:tt [$($m:tt)*] [::$item:ident] @use $($code:tt)*) => {
+        glsl!($data fun @bucket [$($m)*] $item @use $($code)*);
+    };
+
+    //record the import as a dependency of whatever is made public next and splice in its source,
+    //and bring it into scope if no other shader of this module already has
+    (($d:tt $($data:tt)*) fun {{$($deps:tt)*} {$($imported:tt)*}} [$($m:tt)*] $item:ident @use $($code:tt)*) => {
+        glsl!(
+            $item {$($imported)*} @contains_name @if {
+                ($d $($data)*) fun {{$($deps)* [$($m)*::$item]} {$($imported)*}} @push_bucket
+            } @then {
+                [$($m)*] $item @import
+                $d $item _register_name @call
+                ($d $($data)*) fun {{$($deps)* [$($m)*::$item]} {$($imported)* [$item]}} @push_bucket
+            } @else
+            {import_source::<$($m)*::$item>} @src_fn $($code)*
+        );
+    };
+
+    //so that imported structs can be used in uniforms and blocks
+    ([$($m:tt)*] $item:ident @import $($code:tt)*) => {
+        #[allow(unused_imports)] use $($m)*::$item;
+        glsl!($($code)*);
+    };
+
+    //implement GLSLImport using everything imported into or made public in the current shader so far
+    ($data:tt {$src:expr} $name:ident @import_impl $($code:tt)*) => {
+        glsl!($data fun @bucket {$src} $name @import_impl $($code)*);
+    };
+    ($data:tt fun {{$([$($dep:tt)*])*} $imported:tt} {$src:expr} $name:ident @import_impl $($code:tt)*) => {
+        unsafe impl GLSLImport for $name {
+            const SRC: &'static str = $src;
+
+            #[allow(unused_mut)]
+            fn import_dependencies(mut src: String) -> String {
+                $(src = import_source::<$($dep)*>(src);)*
+                src
+            }
+        }
+        glsl!($data fun {{$([$($dep)*])* [$name]} $imported} @push_bucket $($code)*);
+    };
+
     //
     //Structs
     //
@@ -548,6 +896,7 @@ macro_rules! glsl {
         glsl!(
             {$($fields)*} @decl_list $name {$($fields)*} {$(#[$attr])* pub} @struct
             $data
+            {<$name as GLSLStruct>::SRC} $name @import_impl
             {concat!(" ", glsl!(@stringify struct $name {$($fields)*}))} @src_str
             @parse $($code)*
         );
@@ -585,7 +934,7 @@ macro_rules! glsl {
         glsl!(
             [($($params)*)] @ty {[$ty] @ty} @eval {glsl!(@stringify $ty $name($($params)*) {$($content)*})} $name @fun
             $data
-            //[$name] fun @add_to @ignore
+            {glsl!(@stringify $ty $name($($params)*) {$($content)*})} $name @import_impl
             {concat!(" ", glsl!(@stringify $ty $name($($params)*) {$($content)*}))} @src_str
             @parse $($code)*);
     };
@@ -631,6 +980,37 @@ macro_rules! glsl {
         );
     };
 
+    //
+    //Subroutines
+    //
+
+    //we found a subroutine type!
+    ($data:tt @parse subroutine $ret:ident $T:ident($($params:tt)*); $($code:tt)*) => {
+        glsl!($data
+            {concat!(" ", glsl!(@stringify subroutine $ret $T($($params)*);))} @src_str
+            [$T] @subroutine
+            @parse $($code)*
+        );
+    };
+
+    //we found a function for one or more subroutine types!
+    ($data:tt @parse subroutine($($T:ident),+) $ret:ident $f:ident($($params:tt)*) {$($content:tt)*} $($code:tt)*) => {
+        glsl!($data
+            {concat!(" ", glsl!(@stringify subroutine($($T),+) $ret $f($($params)*) {$($content)*}))} @src_str
+            $([$T $f] @subroutine)+
+            @parse $($code)*
+        );
+    };
+
+    //we found a subroutine uniform!
+    ($data:tt @parse subroutine uniform $S:ident $name:ident; $($code:tt)*) => {
+        glsl!($data
+            {concat!(" ", stringify!(subroutine uniform $S $name;), "\n")} @src_str
+            [$S] $name @subroutine_uniform
+            @parse $($code)*
+        );
+    };
+
     //
     //Finding bind points, uniforms, and attributes
     //
@@ -684,6 +1064,79 @@ macro_rules! glsl {
         );
     };
 
+    //
+    //Images
+    //
+
+    //we found a uniform with a layout, which might be an image (with the memory qualifiers on either side of "uniform")
+    ($data:tt @parse layout($($params:tt)*) uniform $q1:ident $q2:ident $ty:ident $name:ident; $($code:tt)*) => {
+        glsl!($data [$ty $name] {$q1 $q2} ($($params)*) {uniform $q1 $q2 $ty $name;} @layout_uniform $($code)*);
+    };
+    ($data:tt @parse layout($($params:tt)*) uniform $q:ident $ty:ident $name:ident; $($code:tt)*) => {
+        glsl!($data [$ty $name] {$q} ($($params)*) {uniform $q $ty $name;} @layout_uniform $($code)*);
+    };
+    ($data:tt @parse layout($($params:tt)*) $q1:ident $q2:ident uniform $ty:ident $name:ident; $($code:tt)*) => {
+        glsl!($data [$ty $name] {$q1 $q2} ($($params)*) {$q1 $q2 uniform $ty $name;} @layout_uniform $($code)*);
+    };
+    ($data:tt @parse layout($($params:tt)*) $q:ident uniform $ty:ident $name:ident; $($code:tt)*) => {
+        glsl!($data [$ty $name] {$q} ($($params)*) {$q uniform $ty $name;} @layout_uniform $($code)*);
+    };
+    ($data:tt @parse layout($($params:tt)*) uniform $ty:ident $name:ident; $($code:tt)*) => {
+        glsl!($data [$ty $name] {} ($($params)*) {uniform $ty $name;} @layout_uniform $($code)*);
+    };
+
+    //images get bound to an image unit at each draw, and anything else gets parsed like before
+    ($data:tt [$ty:ident $name:ident] {$($q:ident)*} ($($params:tt)*) {$($decl:tt)*} @layout_uniform $($code:tt)*) => {
+        glsl!(
+            $data [$ty] @is_image @if {
+                {concat!(" ", stringify!(layout($($params)*) $($decl)*), "\n")} @src_str
+                [$ty $name] {$($q)*} ReadWrite @image_access ($($params)*)
+                @parse
+            } @then {
+                {glsl!(@stringify layout)} @src_str @parse ($($params)*) $($decl)*
+            } @else $($code)*
+        );
+    };
+
+    //find the access from the memory qualifiers
+    ($data:tt $img:tt {readonly $($q:ident)*} $A:ident @image_access $($code:tt)*) => {
+        glsl!($data $img {$($q)*} Read @image_access $($code)*);
+    };
+    ($data:tt $img:tt {writeonly $($q:ident)*} $A:ident @image_access $($code:tt)*) => {
+        glsl!($data $img {$($q)*} Write @image_access $($code)*);
+    };
+    ($data:tt $img:tt {$q0:ident $($q:ident)*} $A:ident @image_access $($code:tt)*) => {
+        glsl!($data $img {$($q)*} $A @image_access $($code)*);
+    };
+    ($data:tt $img:tt {} $A:ident @image_access ($($params:tt)*) $($code:tt)*) => {
+        glsl!($data $img $A {} {} ($($params)*) @image_layout $($code)*);
+    };
+
+    //find the format and binding from the layout parameters
+    ($data:tt $img:tt $A:ident $fmt:tt {} (binding = $n:literal $(, $($params:tt)*)?) @image_layout $($code:tt)*) => {
+        glsl!($data $img $A $fmt {$n} ($($($params)*)?) @image_layout $($code)*);
+    };
+    ($data:tt $img:tt $A:ident $fmt:tt $n:tt ($k:ident = $v:tt $(, $($params:tt)*)?) @image_layout $($code:tt)*) => {
+        glsl!($data $img $A $fmt $n ($($($params)*)?) @image_layout $($code)*);
+    };
+    ($data:tt $img:tt $A:ident {} $n:tt ($f:ident $(, $($params:tt)*)?) @image_layout $($code:tt)*) => {
+        glsl!($data $img $A {$f} $n ($($($params)*)?) @image_layout $($code)*);
+    };
+    ($data:tt [$ty:ident $name:ident] $A:ident {$f:ident} {$n:literal} () @image_layout $($code:tt)*) => {
+        glsl!([$name: $ty<$A> = $f @ $n] $data img @add_to @ignore $($code)*);
+    };
+    ($data:tt [$ty:ident $name:ident] $A:ident {} $n:tt () @image_layout $($code:tt)*) => {
+        compile_error!(concat!("Image uniform \"", stringify!($name), "\" needs a format layout qualifier"));
+    };
+    ($data:tt [$ty:ident $name:ident] $A:ident $fmt:tt {} () @image_layout $($code:tt)*) => {
+        compile_error!(concat!("Image uniform \"", stringify!($name), "\" needs a binding layout qualifier"));
+    };
+    ($data:tt [$ty:ident $name:ident] $A:ident $fmt:tt $n:tt ($($params:tt)*) @image_layout $($code:tt)*) => {
+        compile_error!(concat!(
+            "Invalid layout qualifiers for image uniform \"", stringify!($name), "\": ", stringify!($($params)*)
+        ));
+    };
+
     //we found a uniform!
     ($data:tt @parse uniform $ty:ident $name:ident $($code:tt)*) => {
         glsl!($data {concat!(" ", stringify!(uniform $ty))} @src_str uniform $ty @var $name $($code)*);
@@ -705,6 +1158,85 @@ macro_rules! glsl {
         );
     };
 
+    //we found an attribute with an explicit location!
+    ($data:tt @parse layout(location = $n:literal) in $ty:ident $name:ident $($code:tt)*) => {
+        glsl!(
+            $data @is_vertex @if {
+                {concat!(" ", stringify!(layout(location = $n) in $ty))} @src_str
+                input $name [$n] @location
+                attribute $ty @var $name
+            } @then {
+                {concat!(" ", stringify!(layout(location = $n) in $ty $name))} @src_str @parse
+            } @else $($code)*
+        );
+    };
+
+    //we found a fragment output with an explicit location!
+    ($data:tt @parse layout(location = $n:literal) out $ty:ident $name:ident $($code:tt)*) => {
+        glsl!(
+            $data @is_fragment @if {
+                output $name [$n] @location
+            } @then {} @else
+            {concat!(" ", stringify!(layout(location = $n) out $ty $name))} @src_str @parse $($code)*
+        );
+    };
+
+    //we found the work group size of a compute shader!
+    ($data:tt @parse layout($($params:tt)*) in; $($code:tt)*) => {
+        glsl!($data
+            {concat!(" ", stringify!(layout($($params)*) in;), "\n")} @src_str
+            [1] [1] [1] ($($params)*) @local_size
+            @parse $($code)*
+        );
+    };
+
+    ($data:tt [$x:tt] [$y:tt] [$z:tt] (local_size_x = $n:literal $(, $($params:tt)*)?) @local_size $($code:tt)*) => {
+        glsl!($data [$n] [$y] [$z] ($($($params)*)?) @local_size $($code)*);
+    };
+    ($data:tt [$x:tt] [$y:tt] [$z:tt] (local_size_y = $n:literal $(, $($params:tt)*)?) @local_size $($code:tt)*) => {
+        glsl!($data [$x] [$n] [$z] ($($($params)*)?) @local_size $($code)*);
+    };
+    ($data:tt [$x:tt] [$y:tt] [$z:tt] (local_size_z = $n:literal $(, $($params:tt)*)?) @local_size $($code:tt)*) => {
+        glsl!($data [$x] [$y] [$n] ($($($params)*)?) @local_size $($code)*);
+    };
+    ($data:tt [$x:tt] [$y:tt] [$z:tt] (local_size_variable $($params:tt)*) @local_size $($code:tt)*) => {
+        glsl!($data local {variable} @set_bucket $($code)*);
+    };
+    ($data:tt [$x:tt] [$y:tt] [$z:tt] () @local_size $($code:tt)*) => {
+        glsl!($data local {[$x] [$y] [$z]} @set_bucket $($code)*);
+    };
+
+    //any other input layout is only part of the source, except for the input primitive of geometry shaders
+    ($data:tt [$x:tt] [$y:tt] [$z:tt] ($($params:tt)*) @local_size $($code:tt)*) => {
+        glsl!($data @is_geometry @if { ($($params)*) @geometry_input } @then {} @else $($code)*);
+    };
+
+    ($data:tt ($k:ident = $v:tt $(, $($params:tt)*)?) @geometry_input $($code:tt)*) => {
+        glsl!($data ($($($params)*)?) @geometry_input $($code)*);
+    };
+    ($data:tt ($prim:ident $(, $($params:tt)*)?) @geometry_input $($code:tt)*) => {
+        glsl!($data [$prim] [,,,,,,] @set_flag $($code)*);
+    };
+    ($data:tt () @geometry_input $($code:tt)*) => { glsl!($data $($code)*); };
+
+    //we found the size of the output patches of a tessellation control shader!
+    ($data:tt @parse layout(vertices = $n:literal) out; $($code:tt)*) => {
+        glsl!($data
+            {concat!(" ", stringify!(layout(vertices = $n) out;), "\n")} @src_str
+            [$n] [,,,,,] @set_flag
+            @parse $($code)*
+        );
+    };
+
+    //we found an output to capture with transform feedback!
+    ($data:tt @parse @Capture out $ty:ident $name:ident; $($code:tt)*) => {
+        glsl!($data
+            {concat!(" ", stringify!(out $ty $name;), "\n")} @src_str
+            [$ty] $name @capture
+            @parse $($code)*
+        );
+    };
+
     //we found the next code segment!
     ($data:tt @parse @$next:ident $($code:tt)*) => {
         glsl!($data @src @shader @$next $($code)*);
@@ -789,7 +1321,7 @@ macro_rules! glsl {
             }
 
             //start the processing with 3 data buckets (uniforms, attributes, and source code)
-            glsl!(($d @uni {} @attr {} @block {} @src {} @flags {[false] [false] [false]} @fun {} @current {} ) @shader $($code)*);
+            glsl!(($d @uni {} @attr {} @block {} @src {} @flags {[false] [false] [false] [false] [false] [false] [false]} @xfb {} @loc {} @sub {} @subuni {} @img {} @local {} @fun {{} {}} @current {} ) @shader $($code)*);
         }
 
         //get the other shaders (if any)
@@ -815,28 +1347,34 @@ macro_rules! glsl {
         <$ty as GLSLType>::first_element_name($var + "." + stringify!($name))
     };
 
-    ({$([$name:ident: $ty:ty])*} $struct_name:ident {$($src:tt)*} {$($mod:tt)*} @struct $($code:tt)*) => {
+    ({$([$name:ident: $ty:ty $(=> $gty:ty)?])*} $struct_name:ident {$($src:tt)*} {$($mod:tt)*} @struct $($code:tt)*) => {
         #[repr(C)]
         #[derive(Clone, Copy, PartialEq, Debug, Default)]
         $($mod)* struct $struct_name {
             $(pub $name: $ty),*
         }
 
-        unsafe impl AlignedVec4 for $struct_name where ($($ty),*): AlignedVec4 {}
-        unsafe impl Layout<std140> for $struct_name where Self:AlignedVec4, $($ty: Layout<std140>),* {}
-        unsafe impl Layout<std430> for $struct_name where $($ty: Layout<std430>),* {}
+        //normalized fields only exist on the rust side, so a struct with them can only be used
+        //for vertex data and not in uniforms or blocks
+        glsl!{
+            [$($($gty)?)*] @empty @if {[
+                unsafe impl AlignedVec4 for $struct_name where ($($ty),*): AlignedVec4 {}
+                unsafe impl Layout<std140> for $struct_name where Self:AlignedVec4, $($ty: Layout<std140>),* {}
+                unsafe impl Layout<std430> for $struct_name where $($ty: Layout<std430>),* {}
+            ] @quote} @then {@ret} @else
+        }
 
         //methods for getting attribute arrays from buffers
-        impl $struct_name where $($ty: GLSLType + GLSLData<$ty>),* {
+        impl $struct_name where $(glsl!(@field_ty [$ty] $([$gty])?): GLSLType, $ty: AttributeData<glsl!(@field_ty [$ty] $([$gty])?)>),* {
 
             #[inline]
-            pub fn get_attrib_arrays<'a, A:BufferAccess>(buf: &'a Buffer<[Self], A>) -> ($(AttribArray<'a, $ty>),*) {
+            pub fn get_attrib_arrays<'a, A:BufferAccess>(buf: &'a Buffer<[Self], A>) -> ($(AttribArray<'a, glsl!(@field_ty [$ty] $([$gty])?)>),*) {
                 unsafe {
                     use std::mem::*;
                     let uninit = ::std::mem::MaybeUninit::<Self>::uninit();
                     let start = uninit.as_ptr() as *const u8;
                     let arrays = (
-                        $(buf.get_attrib_array::<$ty, $ty>(
+                        $(buf.get_attrib_array::<glsl!(@field_ty [$ty] $([$gty])?), $ty>(
                             //get the byte offset from the field to the start of the object
                             (&uninit.assume_init_ref().$name as *const _ as *const u8).offset_from(start) as usize
                         )),*
@@ -848,7 +1386,7 @@ macro_rules! glsl {
             }
 
             #[inline]
-            pub fn get_attributes<'a, A:BufferAccess>(buf: &'a Buffer<[Self], A>) -> ($(Attribute<'a, $ty>),*) {
+            pub fn get_attributes<'a, A:BufferAccess>(buf: &'a Buffer<[Self], A>) -> ($(Attribute<'a, glsl!(@field_ty [$ty] $([$gty])?)>),*) {
                 let ($($name),*) = Self::get_attrib_arrays(buf);
                 ($(Attribute::Array($name)),*)
             }
@@ -856,53 +1394,91 @@ macro_rules! glsl {
         }
 
 
+        impl $crate::mesh::MeshVertex for $struct_name {
+            #[allow(unused_variables)]
+            fn set_field(&mut self, field: &str, data: &[f32]) -> bool {
+                $(if field == stringify!($name) { return $crate::mesh::ChannelData::set_channel(&mut self.$name, data); })*
+                false
+            }
+        }
+
         unsafe impl GLSLStruct for $struct_name {
-            const SRC: &'static str = stringify!(struct $struct_name {$($src)*};);
+            const SRC: &'static str = concat!(glsl!(@stringify struct $struct_name {$($src)*}), ";");
         }
 
-        unsafe impl GLSLType for $struct_name where $($ty: GLSLType),* {
-            type AttributeFormat = UnsupportedFormat;
+        glsl!{
+            [$($($gty)?)*] @empty @if {[
+                unsafe impl GLSLType for $struct_name where $($ty: GLSLType),* {
+                    type AttributeFormat = UnsupportedFormat;
 
-            unsafe fn load_uniforms(id: GLint, data: &[Self]) {
+                    unsafe fn load_uniforms(id: GLint, data: &[Self]) {
 
-                for x in data {
-                    #[allow(unused_variables)]
-                    #[allow(unused_mut)]
-                    let mut i = id;
+                        for x in data {
+                            #[allow(unused_variables)]
+                            #[allow(unused_mut)]
+                            let mut i = id;
 
-                    $(
-                        <$ty as GLSLType>::load_uniform(i, &x.$name);
-                        *(&mut i) = {i + <$ty as GLSLType>::uniform_locations() as GLint};
-                    )*
-                }
+                            $(
+                                <$ty as GLSLType>::load_uniform(i, &x.$name);
+                                *(&mut i) = {i + <$ty as GLSLType>::uniform_locations() as GLint};
+                            )*
+                        }
 
 
-            }
+                    }
 
-            unsafe fn get_uniform(p: GLuint, id:GLint) -> Self {
-                let mut value = ::std::mem::MaybeUninit::<Self>::uninit();
+                    unsafe fn get_uniform(p: GLuint, id:GLint) -> Self {
+                        let mut value = ::std::mem::MaybeUninit::<Self>::uninit();
 
-                #[allow(unused_variables)]
-                #[allow(unused_mut)]
-                let mut i = id;
+                        #[allow(unused_variables)]
+                        #[allow(unused_mut)]
+                        let mut i = id;
 
-                $(
-                    (*value.as_mut_ptr()).$name = <$ty as GLSLType>::get_uniform(p, i);
-                    *(&mut i) = {i + <$ty as GLSLType>::uniform_locations() as GLint};
-                )*
+                        $(
+                            (*value.as_mut_ptr()).$name = <$ty as GLSLType>::get_uniform(p, i);
+                            *(&mut i) = {i + <$ty as GLSLType>::uniform_locations() as GLint};
+                        )*
 
-                value.assume_init()
-            }
+                        value.assume_init()
+                    }
 
-            #[inline]
-            fn uniform_locations() -> GLuint {
-                0 $( + <$ty as GLSLType>::uniform_locations())*
-            }
+                    #[inline]
+                    fn uniform_locations() -> GLuint {
+                        0 $( + <$ty as GLSLType>::uniform_locations())*
+                    }
 
-            #[inline]
-            fn first_element_name(var: String) -> String {
-                glsl!(@struct_first_name var {$([$name: $ty])*})
-            }
+                    #[inline]
+                    fn texture_units() -> GLuint {
+                        0 $( + <$ty as GLSLType>::texture_units())*
+                    }
+
+                    unsafe fn assign_texture_units(id: GLint, first: GLuint) {
+                        #[allow(unused_variables)]
+                        #[allow(unused_mut)]
+                        let (mut i, mut unit) = (id, first);
+                        $(
+                            <$ty as GLSLType>::assign_texture_units(i, unit);
+                            *(&mut i) = {i + <$ty as GLSLType>::uniform_locations() as GLint};
+                            *(&mut unit) = {unit + <$ty as GLSLType>::texture_units()};
+                        )*
+                    }
+
+                    unsafe fn bind_textures(&self, first: GLuint) {
+                        #[allow(unused_variables)]
+                        #[allow(unused_mut)]
+                        let mut unit = first;
+                        $(
+                            self.$name.bind_textures(unit);
+                            *(&mut unit) = {unit + <$ty as GLSLType>::texture_units()};
+                        )*
+                    }
+
+                    #[inline]
+                    fn first_element_name(var: String) -> String {
+                        glsl!(@struct_first_name var {$([$name: $ty])*})
+                    }
+                }
+            ] @quote} @then {@ret} @else
         }
 
         glsl!($($code)*);
@@ -919,79 +1495,189 @@ macro_rules! glsl {
         @attr {$([$aname:ident: $a_ty:ty])*}
         @block {$([$block:ident<$I:ident, $L:ident, $A:ident>: $b_ty:ty])*}
         @src {$([$shdr:ident=$src:expr])*}
-        @flags {[$vert:tt] [$frag:tt] [$compute:tt]}
+        @flags {[$vert:tt] [$frag:tt] [$compute:tt] [$separable:tt] [$tess:tt] [$patch:tt] [$geom:tt]}
+        @xfb {$([$cname:ident<$CT:ident>: $c_ty:ty])*}
+        @loc {$($loc:tt)*}
+        @sub {$($sub:tt)*}
+        @subuni {$([$sname:ident: $S:ident = $sstage:ident])*}
+        @img {$([$iname:ident: $ity:ident<$IA:ident> = $ifmt:ident @ $ibind:literal])*}
+        @local {$($local:tt)*}
         $($ignore:tt)*
     ) @create) =>
     {
 
-        glsl!{[$vert] [$frag] @and [$compute] @or @if {[
+        glsl!(@subroutines {$($sub)*} {$($sub)*});
+
+        ///The locations given by `layout(location = N)` for the vertex inputs and fragment outputs
+        pub mod location {
+
+            ///The locations of the vertex inputs
+            #[allow(non_upper_case_globals)]
+            pub mod input {
+                #[allow(unused_imports)] use super::super::*;
+                $(glsl!(@location_const input $loc);)*
+            }
+
+            ///The locations of the fragment outputs
+            #[allow(non_upper_case_globals)]
+            pub mod output {
+                #[allow(unused_imports)] use super::super::*;
+                $(glsl!(@location_const output $loc);)*
+            }
+
+        }
+
+        glsl!{[$($cname)*] @empty [true] @xor [$frag] @or [$vert] @and [$compute] @or [$separable] @or @if {[
 
-            //note: Program::resource, Program::uniform, and Program::subroutine are named as such
-            //in part because those identifiers are reserved keywords in GLSL, and so, we don't
-            //need to worry about a name collision with any of the other fields
+            //note: Program::resource, Program::uniform, Program::attribute, and Program::subroutine
+            //are named as such in part because those identifiers are reserved keywords in GLSL, and
+            //so, we don't need to worry about a name collision with any of the other fields
 
 
-            pub struct Program {
+            pub struct Program<'a> {
                 resource: ProgramID,
+
+                //the lifetime of the textures that the samplers borrow, so that they can't be deleted
+                //while the program can still draw with them
+                #[allow(dead_code)]
+                external: ::std::marker::PhantomData<&'a ()>,
+
+                //the VAO that attributes get loaded into for plain draw calls, since core profiles
+                //require one to be bound
+                #[allow(dead_code)]
+                attribute: Option<RawVertexArray>,
+
                 $($aname: AttributeLocation,)*
                 $($block: $I<$L, $b_ty>,)*
 
                 #[allow(dead_code)]
                 uniform: [UniformLocation; glsl!([$($uname)*] @count @quote)],
                 $(pub $uname: Uniform<$u_ty>,)*
+
+                #[allow(dead_code)]
+                subroutine: SubroutineUniforms,
+                $(pub $sname: Subroutine<$S>,)*
             }
 
+            ///Whether this program was declared `@Separable`, so that it can be used in a [ProgramPipeline]
+            pub const SEPARABLE: bool = $separable;
+
+            glsl!(@local_size_const $($local)*);
+
+            ///Whether this program has a tessellation evaluation shader, and thus, can only draw patches
+            pub const TESSELLATION: bool = $tess;
+
+            ///The input primitive of the geometry shader, if there is one
+            pub const GEOMETRY_INPUT: Option<GeometryInput> = glsl!(@geometry_primitive $geom);
+
+            glsl!(@patch_vertices_const $patch);
+
             #[inline]
-            pub fn init(context: &GLProvider) -> Result<self::Program, GLError> {
+            pub fn init<'a>(context: &GLProvider) -> Result<self::Program<'a>, GLError> {
                 <self::Program as $crate::program::Program>::init(context)
             }
 
-            unsafe impl $crate::program::Program for self::Program {
+            unsafe impl<'a> $crate::program::Program for self::Program<'a> {
+                #[inline]
+                fn init(context: &GLProvider) -> Result<Self, GLError> { init_with_locations(context, &[]) }
+            }
 
-                fn init(context: &GLProvider) -> Result<Self, GLError> {
-                    #[allow(unused_unsafe)]
-                    unsafe {
+            ///
+            ///Creates the program with the given attributes bound to fixed locations before linking,
+            ///so that programs using the same locations can share vertex arrays
+            ///
+            pub fn init_with_locations<'a>(
+                context: &GLProvider, locations: &[(&str, GLuint)]
+            ) -> Result<self::Program<'a>, GLError> {
+                //the sources are temporaries, so they're built as part of each call
+                let captures: &[&str] = &[$(stringify!($cname)),*];
+                let p = if SEPARABLE {
+                    ProgramID::from_source_separable(context, vec![$(($src, ShaderType::$shdr)),*], captures, locations)?
+                } else {
+                    ProgramID::from_source_with_locations(context, vec![$(($src, ShaderType::$shdr)),*], captures, locations)?
+                };
+                Ok(from_resource(context, p))
+            }
 
-                        #[allow(unused_variables)]
-                        #[allow(unused_mut)]
-                        let mut i = 0;
+            ///
+            ///Creates the program using a linked binary in the given directory if there is one,
+            ///compiling and saving it there otherwise
+            ///
+            ///See [ProgramID::from_source_cached] for details.
+            ///
+            pub fn init_cached<'a, P:AsRef<::std::path::Path>>(
+                context: &GLProvider, cache_dir: P, locations: &[(&str, GLuint)]
+            ) -> Result<self::Program<'a>, GLError> {
+                let captures: &[&str] = &[$(stringify!($cname)),*];
+                let p = if SEPARABLE {
+                    ProgramID::from_source_separable_cached(
+                        context, vec![$(($src, ShaderType::$shdr)),*], captures, locations, cache_dir
+                    )?
+                } else {
+                    ProgramID::from_source_cached(context, vec![$(($src, ShaderType::$shdr)),*], captures, locations, cache_dir)?
+                };
+                Ok(from_resource(context, p))
+            }
 
+            fn from_resource<'a>(context: &GLProvider, p: ProgramID) -> self::Program<'a> {
+                #[allow(unused_unsafe)]
+                unsafe {
 
-                        let p = ProgramID::from_source(context, vec![$(($src, ShaderType::$shdr)),*])?;
-                        let uniforms =
-                        [$(
-                            match UniformLocation::get(
-                                &p, <$u_ty as GLSLType>::first_element_name(stringify!($uname).to_owned()).as_str()
-                            ) {
-                                Ok(loc) => loc,
-                                Err(loc) => loc
-                            }
-                        ),*];
+                    #[allow(unused_variables)]
+                    #[allow(unused_mut)]
+                    let mut i = 0;
 
-                        #[allow(unused_mut)]
-                        let mut program = self::Program {
+                    let uniforms =
+                    [$(
+                        match UniformLocation::get(
+                            &p, <$u_ty as GLSLType>::first_element_name(stringify!($uname).to_owned()).as_str()
+                        ) {
+                            Ok(loc) => loc,
+                            Err(loc) => loc
+                        }
+                    ),*];
 
-                            $(
-                                $aname: match AttributeLocation::get(&p, stringify!($aname)) {
-                                    Ok(loc) => loc,
-                                    Err(loc) => loc
-                                },
-                            )*
+                    #[allow(unused_mut)]
+                    let mut program = self::Program {
 
-                            $($block: $I::get(&p, stringify!($block)),)*
+                        $(
+                            $aname: match AttributeLocation::get(&p, stringify!($aname)) {
+                                Ok(loc) => loc,
+                                Err(loc) => loc
+                            },
+                        )*
 
-                            $(
-                                $uname: {
-                                    let j = i;
-                                    *&mut i = i+1;
-                                    uniforms[j].get_uniform()
-                                },
-                            )*
+                        $($block: $I::get(&p, stringify!($block)),)*
 
-                            uniform: uniforms,
-                            resource: p
-                        };
+                        $(
+                            $uname: {
+                                let j = i;
+                                *&mut i = i+1;
+                                uniforms[j].get_uniform()
+                            },
+                        )*
 
+                        $(
+                            $sname: match SubroutineLocation::get(&p, ShaderType::$sstage, stringify!($sname)) {
+                                Ok(loc) => loc,
+                                Err(loc) => loc
+                            }.get_subroutine(),
+                        )*
+
+                        uniform: uniforms,
+                        external: ::std::marker::PhantomData,
+                        subroutine: SubroutineUniforms::get(&p),
+                        attribute: if $crate::gl::GenVertexArrays::is_loaded() {
+                            Some(RawVertexArray::gen(context))
+                        } else {
+                            None
+                        },
+                        resource: p
+                    };
+
+                    //separable programs keep the bindings from their source so that they
+                    //don't collide with the blocks of the other programs in a pipeline
+                    if !SEPARABLE {
                         #[allow(unused_variables)]
                         #[allow(unused_mut)]
                         let mut j = 0;
@@ -999,73 +1685,540 @@ macro_rules! glsl {
                             program.$block.set_binding(j);
                             *&mut j = j+1;
                         )*
+                    }
+
+                    //the texture unit of each sampler is kept by the program, so they only need to be set once
+                    {
+                        program.resource.use_program();
 
-                        Ok(program)
+                        #[allow(unused_variables)]
+                        #[allow(unused_mut)]
+                        let (mut k, mut unit) = (0, self::Program::first_texture_unit(&program.resource));
+                        $(
+                            *&mut unit = program.uniform[k].assign_texture_units::<$u_ty>(unit);
+                            *&mut k = k+1;
+                        )*
+
+                        ProgramID::unbind_program();
                     }
+
+                    program
                 }
+            }
 
+            impl<'a> PipelineStage for self::Program<'a> {
+                #[inline] fn program_id(&self) -> &ProgramID { &self.resource }
+                #[inline] unsafe fn load_uniforms(&self) { self::Program::load_uniforms(self) }
             }
 
-            impl self::Program {
+            impl<'a> self::Program<'a> {
+
+                ///Queries the driver for the interface of the linked program
+                #[inline] pub fn reflect(&self) -> ProgramInterface { self.resource.reflect() }
+
+                //separable programs might share the texture units with others in a pipeline
+                #[inline]
+                fn first_texture_unit(p: &ProgramID) -> GLuint {
+                    if SEPARABLE { p.first_texture_unit() } else { 0 }
+                }
 
                 #[inline]
                 unsafe fn load_uniforms(&self) {
                     #[allow(unused_variables)]
                     #[allow(unused_mut)]
                     let mut i = 0;
+                    #[allow(unused_variables)]
+                    #[allow(unused_mut)]
+                    let mut unit = self::Program::first_texture_unit(&self.resource);
                     $(
                         self.uniform[i].load(&self.$uname);
+                        *&mut unit = self.uniform[i].bind_textures(&self.$uname, unit);
                         *&mut i = i+1;
                     )*
+
+                    //these get reset every time the program is bound, so they are always loaded
+                    self.subroutine.load(&[$((self.$sname.location(), self.$sname.index())),*]);
+                }
+
+                glsl! {
+                    [$($cname)*] @empty [true] @xor [$frag] @or [$vert] @and @if {[
+
+                        //fills in the size of the patches declared by the tessellation control shader
+                        //and checks that the mode can be drawn with the stages of this program
+                        #[inline]
+                        fn draw_mode(mode: DrawMode) -> Result<DrawMode, GLError> {
+                            let mode = mode.with_patch_vertices(glsl!(@patch_vertices $patch));
+                            mode.validate_stages(TESSELLATION, GEOMETRY_INPUT)?;
+                            Ok(mode)
+                        }
+
+                        #[inline]
+                        unsafe fn bind_blocks<$($A: BufferAccess),*>(
+                            &self, mode: DrawMode, $($block: &Buffer<$b_ty, $A>,)* $($iname: &Image<'_, $ity, $IA>),*
+                        ) {
+                            mode.set_patch_vertices();
+
+                            //make sure the uniforms are loaded onto the gpu
+                            self.resource.use_program();
+                            self.load_uniforms();
+                            $(self.$block.bind_buffer_range($block);)*
+                            $($iname.bind($ibind, $crate::image_format::$ifmt);)*
+                        }
+
+                        #[inline]
+                        unsafe fn bind_draw<'b $(,$A: BufferAccess)*>(
+                            &self,
+                            mode: DrawMode,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: &Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        )
+                        {
+                            self.bind_blocks(mode, $($block,)* $($iname),*);
+                            if let Some(vao) = &self.attribute { vao.bind(); }
+                            $(self.$aname.load(&$aname);)*
+                        }
+
+                        #[inline]
+                        unsafe fn unbind_draw(&self) {
+                            RawVertexArray::unbind();
+                            $(self.$block.unbind();)*
+                            $(unbind_image($ibind);)*
+                            ProgramID::unbind_program();
+                        }] @quote
+                    } @then {@ret} @else
                 }
 
                 glsl! {
                     [$vert] [$frag] @and @if {[
+
+                        //checks the indices against the draw mode and the vertices in each attribute array
+                        #[inline]
+                        fn check_elements<I: IndexType, E: BufferAccess>(
+                            context: &Context,
+                            mode: DrawMode,
+                            indices: &Buffer<[I], E>,
+                            base_vertex: GLint,
+                            $($aname: &Attribute<'_, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            #[allow(unused_mut)]
+                            let mut vertices: Option<usize> = None;
+                            $(
+                                if let Some(n) = $aname.vertex_count() {
+                                    vertices = Some(vertices.unwrap_or(n).min(n));
+                                }
+                            )*
+                            $crate::program::check_elements(mode, context.restart_index(), indices, base_vertex, vertices)
+                        }
+
+                        ///
+                        ///Builds a [VertexArray] with the given attributes already loaded so that
+                        ///they can be reused across calls to [draw_vao](Self::draw_vao)
+                        ///
+                        pub fn vertex_array<'b>(&self, context: &GLProvider, $($aname: Attribute<'b, $a_ty>),*) -> VertexArray<'b> {
+                            unsafe {
+                                #[allow(unused_mut)]
+                                let mut vao = VertexArray::new(context);
+                                $(vao.load(&self.$aname, $aname);)*
+                                vao
+                            }
+                        }
+
+                        ///
+                        ///Builds a [VertexArray] with only the layout of each attribute set, so that
+                        ///the actual buffers can be swapped out with [VertexArray::bind_vertex_buffer]
+                        ///
+                        pub fn vertex_format<'b>(&self, context: &GLProvider, $($aname: AttribBinding<$a_ty>),*) -> VertexArray<'b> {
+                            unsafe {
+                                #[allow(unused_mut)]
+                                let mut vao = VertexArray::new(context);
+                                $(vao.load_format(&self.$aname, $aname);)*
+                                vao
+                            }
+                        }
+
+                        //the same as draw, but with the attributes read from a prebuilt vertex array
+                        pub fn draw_vao<'b $(,$A: BufferAccess)*>(
+                            &self,
+                            _context: &mut Context,
+                            mode: DrawMode,
+                            count: usize,
+                            vao: &VertexArray<'b>,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            if !vao.has_layout(&[$(self.$aname.id()),*]) {
+                                return Err(GLError::InvalidOperation(
+                                    "Vertex array has a different attribute layout than this program".to_owned()
+                                ));
+                            }
+                            unsafe {
+                                self.bind_blocks(mode, $($block,)* $(&$iname),*);
+                                vao.bind();
+                                $crate::gl::DrawArrays(GLenum::from(mode), 0, count as GLsizei);
+                                self.unbind_draw();
+                            }
+                            Ok(())
+                        }
+
+                        //draws the vertices referenced by each index in the given element buffer
+                        pub fn draw_elements<'b, I: IndexType, E: BufferAccess $(,$A: BufferAccess)*>(
+                            &self,
+                            context: &mut Context,
+                            mode: DrawMode,
+                            indices: &Buffer<[I], E>,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            self.draw_elements_base_vertex(context, mode, indices, 0, $($block,)* $($iname,)* $($aname),*)
+                        }
+
+                        //the same as draw_elements, but with base_vertex added to every index
+                        pub fn draw_elements_base_vertex<'b, I: IndexType, E: BufferAccess $(,$A: BufferAccess)*>(
+                            &self,
+                            context: &mut Context,
+                            mode: DrawMode,
+                            indices: &Buffer<[I], E>,
+                            base_vertex: GLint,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            self::Program::check_elements(context, mode, indices, base_vertex, $(&$aname),*)?;
+                            unsafe {
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                $crate::program::draw_elements(mode, indices, base_vertex);
+                                self.unbind_draw();
+                            }
+                            Ok(())
+                        }
+
+                        //the instanced version of draw_elements_base_vertex, with the per-instance
+                        //arrays starting at base_instance
+                        pub fn draw_elements_instanced<'b, I: IndexType, E: BufferAccess $(,$A: BufferAccess)*>(
+                            &self,
+                            context: &mut Context,
+                            mode: DrawMode,
+                            indices: &Buffer<[I], E>,
+                            instances: usize,
+                            base_vertex: GLint,
+                            base_instance: GLuint,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            self::Program::check_elements(context, mode, indices, base_vertex, $(&$aname),*)?;
+                            unsafe {
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                $crate::program::draw_elements_instanced(
+                                    mode, indices, instances, base_vertex, base_instance
+                                );
+                                self.unbind_draw();
+                            }
+                            Ok(())
+                        }
+
                         pub fn draw<'b $(,$A: BufferAccess)*>(
                             &self,
                             _context: &mut Context,
                             mode: DrawMode,
                             count: usize,
                             $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
                             $($aname: Attribute<'b, $a_ty>),*
-                        )
+                        ) -> Result<(), GLError>
                         {
+                            let mode = self::Program::draw_mode(mode)?;
                             unsafe {
-                                //make sure the uniforms are loaded onto the gpu
-                                self.resource.use_program();
-                                self.load_uniforms();
-                                $(self.$aname.load(&$aname);)*
-                                $(self.$block.bind_buffer_range($block);)*
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
 
                                 // $crate::gl::Flush();
                                 // $crate::gl::Finish();
 
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
-                                $crate::gl::DrawArrays(mode as GLenum, 0, count as GLsizei);
+                                $crate::gl::DrawArrays(GLenum::from(mode), 0, count as GLsizei);
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
 
-                                $(self.$block.unbind();)*
-                                ProgramID::unbind_program();
+                                self.unbind_draw();
+                            }
+                            Ok(())
+                        }
+
+                        //draws only the vertices within the given range
+                        pub fn draw_range<'b $(,$A: BufferAccess)*>(
+                            &self,
+                            _context: &mut Context,
+                            mode: DrawMode,
+                            range: ::std::ops::Range<usize>,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            if range.start > range.end {
+                                return Err(GLError::InvalidOperation(format!("Invalid vertex range: {:?}", range)));
+                            }
+                            $(
+                                if let Some(len) = $aname.vertex_count() {
+                                    if range.end > len {
+                                        return Err(GLError::InvalidOperation(
+                                            format!("Vertex range out of bounds: {} > {}", range.end, len)
+                                        ));
+                                    }
+                                }
+                            )*
+                            unsafe {
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                $crate::gl::DrawArrays(
+                                    GLenum::from(mode), range.start as GLint, (range.end - range.start) as GLsizei
+                                );
+                                self.unbind_draw();
+                            }
+                            Ok(())
+                        }
+
+                        //draws each of the given vertex ranges with one call to glMultiDrawArrays
+                        pub fn multi_draw<'b $(,$A: BufferAccess)*>(
+                            &self,
+                            _context: &mut Context,
+                            mode: DrawMode,
+                            ranges: &[::std::ops::Range<usize>],
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            if ranges.len()==0 { return Ok(()); }
+
+                            let mut end = 0;
+                            for r in ranges {
+                                if r.start > r.end {
+                                    return Err(GLError::InvalidOperation(format!("Invalid vertex range: {:?}", r)));
+                                }
+                                end = end.max(r.end);
+                            }
+                            $(
+                                if let Some(len) = $aname.vertex_count() {
+                                    if end > len {
+                                        return Err(GLError::InvalidOperation(
+                                            format!("Vertex range out of bounds: {} > {}", end, len)
+                                        ));
+                                    }
+                                }
+                            )*
+
+                            let first: Vec<GLint> = ranges.iter().map(|r| r.start as GLint).collect();
+                            let count: Vec<GLsizei> = ranges.iter().map(|r| (r.end - r.start) as GLsizei).collect();
+                            unsafe {
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                $crate::gl::MultiDrawArrays(
+                                    GLenum::from(mode), &first[0] as *const GLint, &count[0] as *const GLsizei,
+                                    ranges.len() as GLsizei
+                                );
+                                self.unbind_draw();
+                            }
+                            Ok(())
+                        }
+
+                        //draws `instances` copies of the vertices, where arrays made with
+                        //AttribArray::per_instance advance per instance instead of per vertex
+                        pub fn draw_instanced<'b $(,$A: BufferAccess)*>(
+                            &self,
+                            _context: &mut Context,
+                            mode: DrawMode,
+                            count: usize,
+                            instances: usize,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            unsafe {
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                $crate::gl::DrawArraysInstanced(
+                                    GLenum::from(mode), 0, count as GLsizei, instances as GLsizei
+                                );
+                                self.unbind_draw();
+                            }
+                            Ok(())
+                        }
+
+                        //draws once for each command in the buffer, where the commands are read straight from gpu memory
+                        //(requires GL 4.0)
+                        pub fn draw_indirect<'b, C: BufferAccess $(,$A: BufferAccess)*>(
+                            &self,
+                            _context: &mut Context,
+                            mode: DrawMode,
+                            commands: &Buffer<[DrawArraysIndirectCommand], C>,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            unsafe {
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                let result = DrawArraysIndirectCommand::draw(mode, commands);
+                                self.unbind_draw();
+                                result
+                            }
+                        }
+
+                        //the same as draw_indirect, but with only one gl call (requires GL 4.3)
+                        pub fn multi_draw_indirect<'b, C: BufferAccess $(,$A: BufferAccess)*>(
+                            &self,
+                            _context: &mut Context,
+                            mode: DrawMode,
+                            commands: &Buffer<[DrawArraysIndirectCommand], C>,
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+                            unsafe {
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                let result = DrawArraysIndirectCommand::multi_draw(mode, commands);
+                                self.unbind_draw();
+                                result
+                            }
+                        }] @quote
+                    } @then {@ret} @else
+                }
+
+                glsl! {
+                    [$($cname)*] @empty [true] @xor [$vert] @and @if {[
+
+                        //draws while capturing the outputs marked with @Capture into the given buffers.
+                        //If `discard` is set, rasterization is disabled so that only the capture is performed.
+                        //Without geometry or tessellation shaders, each buffer must fit every captured vertex
+                        pub fn draw_capture<'b $(,$A: BufferAccess)* $(,$CT)*>(
+                            &self,
+                            _context: &mut Context,
+                            mode: DrawMode,
+                            count: usize,
+                            discard: bool,
+                            $($cname: &mut Buffer<[$CT], impl BufferAccess>,)*
+                            $($block: &Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>,)*
+                            $($aname: Attribute<'b, $a_ty>),*
+                        ) -> Result<(), GLError> where $($CT: AttributeData<$c_ty>),*
+                        {
+                            let mode = self::Program::draw_mode(mode)?;
+
+                            //the number of vertices captured is only known when they come straight from the vertex shader
+                            if !TESSELLATION && GEOMETRY_INPUT.is_none() {
+                                let captured = mode.feedback_vertices(count);
+                                $(
+                                    if $cname.len() < captured {
+                                        return Err(GLError::InvalidOperation(format!(
+                                            "Capturing {} vertices into {} which only holds {}",
+                                            captured, stringify!($cname), $cname.len()
+                                        )));
+                                    }
+                                )*
+                            }
+                            unsafe {
+                                #[allow(unused_mut)]
+                                let mut i = 0;
+                                $(
+                                    debug_assert_eq!(
+                                        <$CT as AttributeData<$c_ty>>::format().size(), ::std::mem::size_of::<$CT>(),
+                                        "Invalid value size for given captured output!"
+                                    );
+                                    IndexedBufferTarget::TransformFeedbackBuffer.bind_buffer($cname, i);
+                                    *&mut i = i+1;
+                                )*
+
+                                self.bind_draw(mode, $($block,)* $(&$iname,)* $($aname),*);
+                                if discard { $crate::gl::Enable($crate::gl::RASTERIZER_DISCARD); }
+
+                                $crate::gl::BeginTransformFeedback(GLenum::from(self.resource.feedback_mode(mode)));
+                                $crate::gl::DrawArrays(GLenum::from(mode), 0, count as GLsizei);
+                                $crate::gl::EndTransformFeedback();
+
+                                if discard { $crate::gl::Disable($crate::gl::RASTERIZER_DISCARD); }
+                                self.unbind_draw();
+
+                                for j in 0..i {
+                                    IndexedBufferTarget::TransformFeedbackBuffer.unbind(j);
+                                }
                             }
+                            Ok(())
                         }] @quote
                     } @then {@ret} @else
                 }
 
                 glsl! {
                     [$compute] @if {[
+
+                        #[inline]
+                        unsafe fn bind_compute<$($A: BufferAccess,)*>(
+                            &self, $($block: &mut Buffer<$b_ty, $A>,)* $($iname: &Image<'_, $ity, $IA>),*
+                        ) {
+                            //make sure the uniforms are loaded onto the gpu
+                            self.resource.use_program();
+                            self.load_uniforms();
+                            $(self.$block.bind_buffer_range($block);)*
+                            $($iname.bind($ibind, $crate::image_format::$ifmt);)*
+                        }
+
+                        #[inline]
+                        unsafe fn unbind_compute(&self, barrier: MemoryBarrier) {
+                            barrier.issue();
+
+                            $(self.$block.unbind();)*
+                            $(unbind_image($ibind);)*
+                            ProgramID::unbind_program();
+                        }
+
+                        //dispatches the given number of work groups, followed by a barrier on all memory accesses
                         pub fn compute<$($A: BufferAccess,)*>(
                             &self,
                             count_x: GLuint, count_y: GLuint, count_z: GLuint,
-                            $($block: &mut Buffer<$b_ty, $A>),*
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        )
+                        {
+                            self.compute_with_barrier(MemoryBarrier::ALL, count_x, count_y, count_z, $($block,)* $($iname),*);
+                        }
+
+                        //the same as compute, but without any memory barrier afterwards
+                        pub fn compute_no_barrier<$($A: BufferAccess,)*>(
+                            &self,
+                            count_x: GLuint, count_y: GLuint, count_z: GLuint,
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        )
+                        {
+                            self.compute_with_barrier(MemoryBarrier::NONE, count_x, count_y, count_z, $($block,)* $($iname),*);
+                        }
+
+                        //the same as compute, but with only the given barrier afterwards
+                        pub fn compute_with_barrier<$($A: BufferAccess,)*>(
+                            &self,
+                            barrier: MemoryBarrier,
+                            count_x: GLuint, count_y: GLuint, count_z: GLuint,
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
                         )
                         {
                             unsafe {
-                                //make sure the uniforms are loaded onto the gpu
-                                self.resource.use_program();
-                                self.load_uniforms();
-                                $(self.$block.bind_buffer_range($block);)*
+                                self.bind_compute($($block,)* $(&$iname),*);
 
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
@@ -1073,15 +2226,136 @@ macro_rules! glsl {
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
                                 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
 
+                                self.unbind_compute(barrier);
+                            }
+                        }
 
-                                $crate::gl::MemoryBarrier($crate::gl::ALL_BARRIER_BITS);
+                        //dispatches with the work group counts stored in a buffer instead of from the cpu
+                        pub fn compute_indirect<C: BufferAccess, $($A: BufferAccess,)*>(
+                            &self,
+                            command: &Buffer<DispatchIndirectCommand, C>,
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        )
+                        {
+                            self.compute_indirect_with_barrier(MemoryBarrier::ALL, command, $($block,)* $($iname),*);
+                        }
 
-                                $(self.$block.unbind();)*
-                                ProgramID::unbind_program();
+                        //the same as compute_indirect, but with only the given barrier afterwards
+                        pub fn compute_indirect_with_barrier<C: BufferAccess, $($A: BufferAccess,)*>(
+                            &self,
+                            barrier: MemoryBarrier,
+                            command: &Buffer<DispatchIndirectCommand, C>,
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        )
+                        {
+                            unsafe {
+                                self.bind_compute($($block,)* $(&$iname),*);
+                                DispatchIndirectCommand::dispatch(command);
+                                self.unbind_compute(barrier);
                             }
                         }] @quote
                     } @then {@ret} @else
                 }
+
+                glsl! {
+                    {$($local)*} @fixed_local_size @if {[
+
+                        //dispatches enough work groups of LOCAL_SIZE to run at least the given number of threads
+                        pub fn dispatch_threads<$($A: BufferAccess,)*>(
+                            &self,
+                            threads_x: GLuint, threads_y: GLuint, threads_z: GLuint,
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        ) -> Result<(), GLError>
+                        {
+                            self.dispatch_threads_with_barrier(
+                                MemoryBarrier::ALL, threads_x, threads_y, threads_z, $($block,)* $($iname),*
+                            )
+                        }
+
+                        //the same as dispatch_threads, but with only the given barrier afterwards
+                        pub fn dispatch_threads_with_barrier<$($A: BufferAccess,)*>(
+                            &self,
+                            barrier: MemoryBarrier,
+                            threads_x: GLuint, threads_y: GLuint, threads_z: GLuint,
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let [x, y, z] = work_group_count([threads_x, threads_y, threads_z], self::LOCAL_SIZE)?;
+                            self.compute_with_barrier(barrier, x, y, z, $($block,)* $($iname),*);
+                            Ok(())
+                        }] @quote
+                    } @then {@ret} @else
+                }
+
+                glsl! {
+                    {$($local)*} @variable_local_size @if {[
+
+                        //dispatches work groups of the given size for a shader with `layout(local_size_variable) in;`
+                        //(requires ARB_compute_variable_group_size)
+                        pub fn compute_group_size<$($A: BufferAccess,)*>(
+                            &self,
+                            count_x: GLuint, count_y: GLuint, count_z: GLuint,
+                            local_size: [GLuint; 3],
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        ) -> Result<(), GLError>
+                        {
+                            self.compute_group_size_with_barrier(
+                                MemoryBarrier::ALL, count_x, count_y, count_z, local_size, $($block,)* $($iname),*
+                            )
+                        }
+
+                        //the same as compute_group_size, but with only the given barrier afterwards
+                        pub fn compute_group_size_with_barrier<$($A: BufferAccess,)*>(
+                            &self,
+                            barrier: MemoryBarrier,
+                            count_x: GLuint, count_y: GLuint, count_z: GLuint,
+                            local_size: [GLuint; 3],
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        ) -> Result<(), GLError>
+                        {
+                            unsafe {
+                                self.bind_compute($($block,)* $(&$iname),*);
+                                let result = dispatch_group_size([count_x, count_y, count_z], local_size);
+                                self.unbind_compute(barrier);
+                                result
+                            }
+                        }
+
+                        //the same as compute_group_size, but with enough work groups to run at least the given number of threads
+                        pub fn dispatch_threads<$($A: BufferAccess,)*>(
+                            &self,
+                            threads_x: GLuint, threads_y: GLuint, threads_z: GLuint,
+                            local_size: [GLuint; 3],
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        ) -> Result<(), GLError>
+                        {
+                            self.dispatch_threads_with_barrier(
+                                MemoryBarrier::ALL, threads_x, threads_y, threads_z, local_size, $($block,)* $($iname),*
+                            )
+                        }
+
+                        //the same as dispatch_threads, but with only the given barrier afterwards
+                        pub fn dispatch_threads_with_barrier<$($A: BufferAccess,)*>(
+                            &self,
+                            barrier: MemoryBarrier,
+                            threads_x: GLuint, threads_y: GLuint, threads_z: GLuint,
+                            local_size: [GLuint; 3],
+                            $($block: &mut Buffer<$b_ty, $A>,)*
+                            $($iname: Image<'_, $ity, $IA>),*
+                        ) -> Result<(), GLError>
+                        {
+                            let [x, y, z] = work_group_count([threads_x, threads_y, threads_z], local_size)?;
+                            self.compute_group_size_with_barrier(barrier, x, y, z, local_size, $($block,)* $($iname),*)
+                        }] @quote
+                    } @then {@ret} @else
+                }
             }] @quote
         } @then {@ret} @else }
 
@@ -114,8 +114,8 @@ macro_rules! glsl_type {
     ({$a:expr} $b1:tt $b2:tt $b3:tt [[$prim:ident; $c1:tt]; $c2:tt] @type $($tail:tt)*) => { glsl_type!({$a} $b1 $b2 $b3 false true $($tail)*); };
 
     //the initial macro call
-    ({$fmt:ty} $name:ident = $($ty:tt)*) => {
-        glsl_type!($($ty)* @align $($ty)* @type {$fmt} {$($ty)*} {gl_builder!(@set $($ty)*)} {gl_builder!(@get $($ty)*)} $name);
+    ({$fmt:ty} $name:ident: $dt:ident = $($ty:tt)*) => {
+        glsl_type!($($ty)* @align $($ty)* @type {$fmt} {$($ty)*} {gl_builder!(@set $($ty)*)} {gl_builder!(@get $($ty)*)} $name $dt);
         glsl_type!(@index $name = $($ty)*);
     };
 
@@ -131,7 +131,7 @@ macro_rules! glsl_type {
         }
     };
 
-    ({$a:expr} $align_vec4:tt $std140:tt $std430:tt $scalar:tt $mat:tt {$fmt:ty} {$prim:ty} {$set:expr} {$get:expr} $name:ident) => {
+    ({$a:expr} $align_vec4:tt $std140:tt $std430:tt $scalar:tt $mat:tt {$fmt:ty} {$prim:ty} {$set:expr} {$get:expr} $name:ident $dt:ident) => {
 
         gl_builder! {
             [$scalar] @not [
@@ -157,6 +157,8 @@ macro_rules! glsl_type {
         unsafe impl GLSLType for $name {
             type AttributeFormat = $fmt;
 
+            #[inline] fn data_type() -> Option<(DataType, usize)> { Some((DataType::$dt, 1)) }
+
             unsafe fn load_uniforms(id: GLint, data: &[Self]){
                 let f = &$set;
                 gl_builder!{
@@ -201,6 +203,7 @@ use gl::*;
 use super::*;
 
 use std::mem::transmute;
+use std::marker::PhantomData;
 use std::ops::*;
 
 #[repr(align(4))]
@@ -237,53 +240,419 @@ impl From<c_bool> for GLboolean { #[inline] fn from(b: c_bool) -> Self {b.0 as G
 #[allow(non_camel_case_types)]
 pub type void = ();
 
+//
+//Compact vertex data types, for the half-float, fixed-point, and packed vertex formats
+//
+
+//shifts off the given number of bits, rounding to the nearest value with ties going to even
+fn round_shift(v: u32, shift: u32) -> u32 {
+    if shift >= 32 { return 0; }
+    let (q, rem, half) = (v >> shift, v & ((1 << shift) - 1), 1 << (shift - 1));
+    if rem > half || (rem == half && (q & 1) != 0) { q + 1 } else { q }
+}
+
+//converts a positive f32 to an unsigned float with a 5 bit exponent and the given number of mantissa
+//bits, so this covers both the 10 and 11 bit floats and the magnitude of a half-float
+fn f32_to_ufloat(f: f32, mbits: u32) -> u32 {
+    let bits = f.to_bits();
+    let exp = ((bits >> 23) & 0xFF) as i32;
+    let man = bits & 0x7FFFFF;
+
+    if exp == 0xFF {
+        //NaN or infinity, with negative infinity clamped to zero
+        if man != 0 { return (0x1F << mbits) | 1; }
+        return if (bits >> 31) != 0 { 0 } else { 0x1F << mbits };
+    }
+    if (bits >> 31) != 0 { return 0; }
+
+    //rounding up can carry into the exponent, which gives the next power of two or infinity as it should
+    let e = exp - 127 + 15;
+    if e >= 0x1F {
+        0x1F << mbits
+    } else if e <= 0 {
+        //denormals
+        round_shift(man | 0x800000, (24 - mbits as i32 - e) as u32)
+    } else {
+        ((e as u32) << mbits) + round_shift(man, 23 - mbits)
+    }
+}
+
+fn ufloat_to_f32(f: u32, mbits: u32) -> f32 {
+    let e = (f >> mbits) & 0x1F;
+    let m = f & ((1 << mbits) - 1);
+    match e {
+        0 => m as f32 * 2.0f32.powi(-14 - mbits as i32),
+        0x1F => if m == 0 { ::std::f32::INFINITY } else { ::std::f32::NAN },
+        _ => f32::from_bits(((e + 112) << 23) | (m << (23 - mbits)))
+    }
+}
+
+///A 16-bit IEEE half-precision float
+#[repr(transparent)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct f16(u16);
+
+impl f16 {
+    #[inline] pub fn from_bits(bits: u16) -> Self { f16(bits) }
+    #[inline] pub fn to_bits(self) -> u16 { self.0 }
+}
+
+impl From<f32> for f16 {
+    #[inline]
+    fn from(f: f32) -> Self {
+        let sign = ((f.to_bits() >> 16) & 0x8000) as u16;
+        f16(sign | f32_to_ufloat(f.abs(), 10) as u16)
+    }
+}
+
+impl From<f16> for f32 {
+    #[inline]
+    fn from(f: f16) -> Self {
+        let abs = ufloat_to_f32((f.0 & 0x7FFF) as u32, 10);
+        if (f.0 & 0x8000) != 0 { -abs } else { abs }
+    }
+}
+
+///A signed 16.16 fixed-point number
+#[repr(transparent)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct fixed(GLfixed);
+
+impl fixed {
+    #[inline] pub fn from_bits(bits: GLfixed) -> Self { fixed(bits) }
+    #[inline] pub fn to_bits(self) -> GLfixed { self.0 }
+}
+
+impl From<f32> for fixed { #[inline] fn from(f: f32) -> Self { fixed((f * 65536.0) as GLfixed) } }
+impl From<fixed> for f32 { #[inline] fn from(f: fixed) -> Self { f.0 as f32 / 65536.0 } }
+
+///Four unsigned integers packed into 10, 10, 10, and 2 bits, with x in the lowest bits
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Packed1010102(GLuint);
+
+impl Packed1010102 {
+    #[inline] pub fn from_bits(bits: GLuint) -> Self { Packed1010102(bits) }
+    #[inline] pub fn to_bits(self) -> GLuint { self.0 }
+
+    ///Packs the given components, discarding any bits that don't fit
+    #[inline]
+    pub fn new(x: u32, y: u32, z: u32, w: u32) -> Self {
+        Packed1010102((x & 0x3FF) | ((y & 0x3FF) << 10) | ((z & 0x3FF) << 20) | ((w & 0x3) << 30))
+    }
+
+    #[inline]
+    pub fn unpack(self) -> [u32; 4] {
+        [self.0 & 0x3FF, (self.0 >> 10) & 0x3FF, (self.0 >> 20) & 0x3FF, self.0 >> 30]
+    }
+}
+
+///Four signed integers packed into 10, 10, 10, and 2 bits, with x in the lowest bits
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct IPacked1010102(GLuint);
+
+impl IPacked1010102 {
+    #[inline] pub fn from_bits(bits: GLuint) -> Self { IPacked1010102(bits) }
+    #[inline] pub fn to_bits(self) -> GLuint { self.0 }
+
+    ///Packs the given components, discarding any bits that don't fit
+    #[inline]
+    pub fn new(x: i32, y: i32, z: i32, w: i32) -> Self {
+        IPacked1010102(
+            (x as u32 & 0x3FF) | ((y as u32 & 0x3FF) << 10) | ((z as u32 & 0x3FF) << 20) | ((w as u32 & 0x3) << 30)
+        )
+    }
+
+    #[inline]
+    pub fn unpack(self) -> [i32; 4] {
+        //shift each component to the top and back down again in order to sign extend it
+        let b = self.0 as i32;
+        [(b << 22) >> 22, (b << 12) >> 22, (b << 2) >> 22, b >> 30]
+    }
+}
+
+///
+///Marks unsigned integer vertex data as normalized, so that the full range of the integer type gets
+///mapped onto `[0,1]` when read as a float
+///
+///For example, a `[Norm<u8>; 4]` is read as a `vec4` color, whereas a raw `[u8; 4]` would
+///be read as values from 0 to 255
+///
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Norm<T>(pub T);
+
+///Marks signed integer vertex data as normalized, so that it gets mapped onto `[-1,1]` when read as a float
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct SNorm<T>(pub T);
+
+macro_rules! impl_norm {
+    ($($Norm:ident<$prim:ident>)*) => {$(
+        impl From<f32> for $Norm<$prim> {
+            #[inline]
+            fn from(f: f32) -> Self {
+                let min = if $prim::MIN==0 { 0.0 } else { -1.0 };
+                $Norm((f.max(min).min(1.0) * $prim::MAX as f32).round() as $prim)
+            }
+        }
+
+        impl From<$Norm<$prim>> for f32 {
+            #[inline]
+            fn from(n: $Norm<$prim>) -> Self { (n.0 as f32 / $prim::MAX as f32).max(-1.0) }
+        }
+    )*}
+}
+
+impl_norm!(Norm<u8> Norm<u16> Norm<u32> SNorm<i8> SNorm<i16> SNorm<i32>);
+
+///Three positive floats packed into 11, 11, and 10 bits, with x in the lowest bits
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Packed111110F(GLuint);
+
+impl Packed111110F {
+    #[inline] pub fn from_bits(bits: GLuint) -> Self { Packed111110F(bits) }
+    #[inline] pub fn to_bits(self) -> GLuint { self.0 }
+
+    ///Packs the given components, where negative values get clamped to zero
+    #[inline]
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Packed111110F(f32_to_ufloat(x, 6) | (f32_to_ufloat(y, 6) << 11) | (f32_to_ufloat(z, 5) << 22))
+    }
+
+    #[inline]
+    pub fn unpack(self) -> [f32; 3] {
+        [
+            ufloat_to_f32(self.0 & 0x7FF, 6),
+            ufloat_to_f32((self.0 >> 11) & 0x7FF, 6),
+            ufloat_to_f32(self.0 >> 22, 5)
+        ]
+    }
+}
+
 //booleans
-glsl_type!({IntFormat} gl_bool = c_bool);
-glsl_type!({IVecFormat} bvec2 = [c_bool; 2]);
-glsl_type!({IVecFormat} bvec3 = [c_bool; 3]);
-glsl_type!({IVecFormat} bvec4 = [c_bool; 4]);
+glsl_type!({IntFormat} gl_bool: Bool = c_bool);
+glsl_type!({IVecFormat} bvec2: BVec2 = [c_bool; 2]);
+glsl_type!({IVecFormat} bvec3: BVec3 = [c_bool; 3]);
+glsl_type!({IVecFormat} bvec4: BVec4 = [c_bool; 4]);
 
 //integers
-glsl_type!({IntFormat} int = GLint);
-glsl_type!({IVecFormat} ivec2 = [GLint; 2]);
-glsl_type!({IVecFormat} ivec3 = [GLint; 3]);
-glsl_type!({IVecFormat} ivec4 = [GLint; 4]);
+glsl_type!({IntFormat} int: Int = GLint);
+glsl_type!({IVecFormat} ivec2: IVec2 = [GLint; 2]);
+glsl_type!({IVecFormat} ivec3: IVec3 = [GLint; 3]);
+glsl_type!({IVecFormat} ivec4: IVec4 = [GLint; 4]);
 
 //unsigned integers
-glsl_type!({IntFormat} uint = GLuint);
-glsl_type!({IVecFormat} uvec2 = [GLuint; 2]);
-glsl_type!({IVecFormat} uvec3 = [GLuint; 3]);
-glsl_type!({IVecFormat} uvec4 = [GLuint; 4]);
+glsl_type!({IntFormat} uint: UInt = GLuint);
+glsl_type!({IVecFormat} uvec2: UVec2 = [GLuint; 2]);
+glsl_type!({IVecFormat} uvec3: UVec3 = [GLuint; 3]);
+glsl_type!({IVecFormat} uvec4: UVec4 = [GLuint; 4]);
 
 //floats
-glsl_type!({FloatFormat} float = GLfloat);
-glsl_type!({VecFormat} vec2 = [GLfloat; 2]);
-glsl_type!({VecFormat} vec3 = [GLfloat; 3]);
-glsl_type!({VecFormat} vec4 = [GLfloat; 4]);
-glsl_type!({[VecFormat; 2]} mat2   = [[GLfloat; 2]; 2]);
-glsl_type!({[VecFormat; 2]} mat2x3 = [[GLfloat; 3]; 2]);
-glsl_type!({[VecFormat; 2]} mat2x4 = [[GLfloat; 4]; 2]);
-glsl_type!({[VecFormat; 3]} mat3x2 = [[GLfloat; 2]; 3]);
-glsl_type!({[VecFormat; 3]} mat3   = [[GLfloat; 3]; 3]);
-glsl_type!({[VecFormat; 3]} mat3x4 = [[GLfloat; 4]; 3]);
-glsl_type!({[VecFormat; 4]} mat4x2 = [[GLfloat; 2]; 4]);
-glsl_type!({[VecFormat; 4]} mat4x3 = [[GLfloat; 3]; 4]);
-glsl_type!({[VecFormat; 4]} mat4   = [[GLfloat; 4]; 4]);
+glsl_type!({FloatFormat} float: Float = GLfloat);
+glsl_type!({VecFormat} vec2: Vec2 = [GLfloat; 2]);
+glsl_type!({VecFormat} vec3: Vec3 = [GLfloat; 3]);
+glsl_type!({VecFormat} vec4: Vec4 = [GLfloat; 4]);
+glsl_type!({[VecFormat; 2]} mat2: Mat2     = [[GLfloat; 2]; 2]);
+glsl_type!({[VecFormat; 2]} mat2x3: Mat2x3 = [[GLfloat; 3]; 2]);
+glsl_type!({[VecFormat; 2]} mat2x4: Mat2x4 = [[GLfloat; 4]; 2]);
+glsl_type!({[VecFormat; 3]} mat3x2: Mat3x2 = [[GLfloat; 2]; 3]);
+glsl_type!({[VecFormat; 3]} mat3: Mat3     = [[GLfloat; 3]; 3]);
+glsl_type!({[VecFormat; 3]} mat3x4: Mat3x4 = [[GLfloat; 4]; 3]);
+glsl_type!({[VecFormat; 4]} mat4x2: Mat4x2 = [[GLfloat; 2]; 4]);
+glsl_type!({[VecFormat; 4]} mat4x3: Mat4x3 = [[GLfloat; 3]; 4]);
+glsl_type!({[VecFormat; 4]} mat4: Mat4     = [[GLfloat; 4]; 4]);
 
 //doubles
-glsl_type!({DoubleFormat} double = GLdouble);
-glsl_type!({DVecFormat} dvec2 = [GLdouble; 2]);
-glsl_type!({DVecFormat} dvec3 = [GLdouble; 3]);
-glsl_type!({DVecFormat} dvec4 = [GLdouble; 4]);
-glsl_type!({[DVecFormat; 2]} dmat2   = [[GLdouble; 2]; 2]);
-glsl_type!({[DVecFormat; 2]} dmat2x3 = [[GLdouble; 3]; 2]);
-glsl_type!({[DVecFormat; 2]} dmat2x4 = [[GLdouble; 4]; 2]);
-glsl_type!({[DVecFormat; 3]} dmat3x2 = [[GLdouble; 2]; 3]);
-glsl_type!({[DVecFormat; 3]} dmat3   = [[GLdouble; 3]; 3]);
-glsl_type!({[DVecFormat; 3]} dmat3x4 = [[GLdouble; 4]; 3]);
-glsl_type!({[DVecFormat; 4]} dmat4x2 = [[GLdouble; 2]; 4]);
-glsl_type!({[DVecFormat; 4]} dmat4x3 = [[GLdouble; 3]; 4]);
-glsl_type!({[DVecFormat; 4]} dmat4   = [[GLdouble; 4]; 4]);
+glsl_type!({DoubleFormat} double: Double = GLdouble);
+glsl_type!({DVecFormat} dvec2: DVec2 = [GLdouble; 2]);
+glsl_type!({DVecFormat} dvec3: DVec3 = [GLdouble; 3]);
+glsl_type!({DVecFormat} dvec4: DVec4 = [GLdouble; 4]);
+glsl_type!({[DVecFormat; 2]} dmat2: DMat2     = [[GLdouble; 2]; 2]);
+glsl_type!({[DVecFormat; 2]} dmat2x3: DMat2x3 = [[GLdouble; 3]; 2]);
+glsl_type!({[DVecFormat; 2]} dmat2x4: DMat2x4 = [[GLdouble; 4]; 2]);
+glsl_type!({[DVecFormat; 3]} dmat3x2: DMat3x2 = [[GLdouble; 2]; 3]);
+glsl_type!({[DVecFormat; 3]} dmat3: DMat3     = [[GLdouble; 3]; 3]);
+glsl_type!({[DVecFormat; 3]} dmat3x4: DMat3x4 = [[GLdouble; 4]; 3]);
+glsl_type!({[DVecFormat; 4]} dmat4x2: DMat4x2 = [[GLdouble; 2]; 4]);
+glsl_type!({[DVecFormat; 4]} dmat4x3: DMat4x3 = [[GLdouble; 3]; 4]);
+glsl_type!({[DVecFormat; 4]} dmat4: DMat4     = [[GLdouble; 4]; 4]);
+
+//
+//Samplers are opaque, so their value is just the texture and sampler objects to bind to their
+//texture unit at each draw, and the only thing actually loaded into the uniform is the unit itself
+//
+
+macro_rules! sampler_type {
+    ($($Kind:ident { $($name:ident: $dt:ident = $target:ident),* })*) => {$($(
+
+        #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+        #[allow(non_camel_case_types)]
+        pub struct $name<'a> {
+            texture: GLuint,
+            sampler: GLuint,
+            borrow: PhantomData<(&'a GLuint, &'a Sampler)>
+        }
+
+        impl<'a> $name<'a> {
+            #[inline] pub fn texture(&self) -> GLuint { self.texture }
+            #[inline] pub fn sampler(&self) -> GLuint { self.sampler }
+
+            fn check_target(target: TextureTarget) -> Result<(), GLError> {
+                if target == TextureTarget::$target { return Ok(()); }
+                Err(GLError::InvalidOperation(format!(
+                    "A {} can't sample a {} but was given a {}", stringify!($name), TextureTarget::$target, target
+                )))
+            }
+        }
+
+        impl<'a, F:$Kind> TryFrom<(&'a Texture<F>, &'a Sampler)> for $name<'a> {
+            type Error = GLError;
+            #[inline]
+            fn try_from((tex, sampler): (&'a Texture<F>, &'a Sampler)) -> Result<Self, GLError> {
+                Self::check_target(tex.target())?;
+                Ok($name { texture: tex.id(), sampler: sampler.id(), borrow: PhantomData })
+            }
+        }
+
+        //reads the texture with its own parameters instead of a sampler's
+        impl<'a, F:$Kind> TryFrom<&'a Texture<F>> for $name<'a> {
+            type Error = GLError;
+            #[inline]
+            fn try_from(tex: &'a Texture<F>) -> Result<Self, GLError> {
+                Self::check_target(tex.target())?;
+                Ok($name { texture: tex.id(), sampler: 0, borrow: PhantomData })
+            }
+        }
+
+        unsafe impl<'a> GLSLType for $name<'a> {
+            type AttributeFormat = UnsupportedFormat;
+
+            #[inline] fn data_type() -> Option<(DataType, usize)> { Some((DataType::$dt, 1)) }
+
+            //the texture is bound at each draw instead
+            #[inline] unsafe fn load_uniforms(_id: GLint, _data: &[Self]) {}
+            #[inline] unsafe fn get_uniform(_p: GLuint, _id: GLint) -> Self { Self::default() }
+
+            #[inline] fn texture_units() -> GLuint {1}
+            #[inline] unsafe fn assign_texture_units(id: GLint, first: GLuint) { Uniform1i(id, first as GLint); }
+            #[inline] unsafe fn bind_textures(&self, first: GLuint) {
+                bind_texture_unit(first, TextureTarget::$target, self.texture, self.sampler);
+            }
+        }
+
+    )*)*};
+}
+
+sampler_type! {
+    FloatSampling {
+        sampler1D: Sampler1D = Texture1D,
+        sampler2D: Sampler2D = Texture2D,
+        sampler3D: Sampler3D = Texture3D,
+        samplerCube: SamplerCube = TextureCubeMap,
+        sampler2DRect: Sampler2DRect = TextureRectangle,
+        sampler1DArray: Sampler1DArray = Texture1DArray,
+        sampler2DArray: Sampler2DArray = Texture2DArray,
+        samplerCubeArray: SamplerCubeArray = TextureCubeMapArray,
+        samplerBuffer: SamplerBuffer = TextureBuffer
+    }
+
+    IntSampling {
+        isampler1D: ISampler1D = Texture1D,
+        isampler2D: ISampler2D = Texture2D,
+        isampler3D: ISampler3D = Texture3D,
+        isamplerCube: ISamplerCube = TextureCubeMap,
+        isampler2DRect: ISampler2DRect = TextureRectangle,
+        isampler1DArray: ISampler1DArray = Texture1DArray,
+        isampler2DArray: ISampler2DArray = Texture2DArray,
+        isamplerCubeArray: ISamplerCubeArray = TextureCubeMapArray,
+        isamplerBuffer: ISamplerBuffer = TextureBuffer
+    }
+
+    UIntSampling {
+        usampler1D: USampler1D = Texture1D,
+        usampler2D: USampler2D = Texture2D,
+        usampler3D: USampler3D = Texture3D,
+        usamplerCube: USamplerCube = TextureCubeMap,
+        usampler2DRect: USampler2DRect = TextureRectangle,
+        usampler1DArray: USampler1DArray = Texture1DArray,
+        usampler2DArray: USampler2DArray = Texture2DArray,
+        usamplerCubeArray: USamplerCubeArray = TextureCubeMapArray,
+        usamplerBuffer: USamplerBuffer = TextureBuffer
+    }
+
+    ShadowSampling {
+        sampler1DShadow: Sampler1DShadow = Texture1D,
+        sampler2DShadow: Sampler2DShadow = Texture2D,
+        samplerCubeShadow: SamplerCubeShadow = TextureCubeMap,
+        sampler2DRectShadow: Sampler2DRectShadow = TextureRectangle,
+        sampler1DArrayShadow: Sampler1DArrayShadow = Texture1DArray,
+        sampler2DArrayShadow: Sampler2DArrayShadow = Texture2DArray,
+        samplerCubeArrayShadow: SamplerCubeArrayShadow = TextureCubeMapArray
+    }
+}
+
+//
+//Images aren't loaded like other uniforms at all, since glsl! binds each one to the unit in its
+//binding qualifier, so these only mark the kind of texture that each image type can be bound to
+//
+
+macro_rules! image_type {
+    ($($Format:ident { $($name:ident = $target:ident),* })*) => {$($(
+
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #[allow(non_camel_case_types)]
+        pub struct $name;
+
+        unsafe impl GLSLImage for $name {
+            type Format = $Format;
+            #[inline] fn target() -> TextureTarget { TextureTarget::$target }
+        }
+
+    )*)*};
+}
+
+image_type! {
+    InternalFormatFloat {
+        image1D = Texture1D,
+        image2D = Texture2D,
+        image3D = Texture3D,
+        imageCube = TextureCubeMap,
+        image2DRect = TextureRectangle,
+        image1DArray = Texture1DArray,
+        image2DArray = Texture2DArray,
+        imageCubeArray = TextureCubeMapArray,
+        imageBuffer = TextureBuffer
+    }
+
+    InternalFormatInt {
+        iimage1D = Texture1D,
+        iimage2D = Texture2D,
+        iimage3D = Texture3D,
+        iimageCube = TextureCubeMap,
+        iimage2DRect = TextureRectangle,
+        iimage1DArray = Texture1DArray,
+        iimage2DArray = Texture2DArray,
+        iimageCubeArray = TextureCubeMapArray,
+        iimageBuffer = TextureBuffer
+    }
+
+    InternalFormatUInt {
+        uimage1D = Texture1D,
+        uimage2D = Texture2D,
+        uimage3D = Texture3D,
+        uimageCube = TextureCubeMap,
+        uimage2DRect = TextureRectangle,
+        uimage1DArray = Texture1DArray,
+        uimage2DArray = Texture2DArray,
+        uimageCubeArray = TextureCubeMapArray,
+        uimageBuffer = TextureBuffer
+    }
+}
 
 
 macro_rules! impl_array_type {
@@ -314,6 +683,27 @@ macro_rules! impl_array_type {
                 #[inline] fn uniform_locations() -> GLuint { T::uniform_locations() * $num }
                 #[inline] fn first_element_name(var: String) -> String { T::first_element_name(var + "[0]") }
 
+                #[inline] fn texture_units() -> GLuint { T::texture_units() * $num }
+
+                unsafe fn assign_texture_units(id: GLint, first: GLuint) {
+                    if T::texture_units() == 0 { return; }
+                    for i in 0..$num {
+                        T::assign_texture_units(id + (i * T::uniform_locations()) as GLint, first + i * T::texture_units());
+                    }
+                }
+
+                unsafe fn bind_textures(&self, first: GLuint) {
+                    if T::texture_units() == 0 { return; }
+                    for i in 0..$num {
+                        self[i as usize].bind_textures(first + i * T::texture_units());
+                    }
+                }
+
+                //arrays of arrays are reflected as separate arrays, so only the innermost level has a type
+                #[inline] fn data_type() -> Option<(DataType, usize)> {
+                    match T::data_type() { Some((ty, 1)) => Some((ty, $num)), _ => None }
+                }
+
             }
 
             unsafe impl<T:AlignedVec4> AlignedVec4 for [T; $num] {}
@@ -412,6 +802,26 @@ macro_rules! impl_tuple_type {
             #[inline] fn uniform_locations() -> GLuint { 0 $(+ $T::uniform_locations())* }
             #[inline] fn first_element_name(var: String) -> String {impl_tuple_type!(var @first $($T)*)}
 
+            #[inline] fn texture_units() -> GLuint { 0 $(+ $T::texture_units())* }
+
+            unsafe fn assign_texture_units(id: GLint, first: GLuint) {
+                let (mut i, mut unit) = (id, first);
+                $(
+                    $T::assign_texture_units(i, unit);
+                    *(&mut i) = i + $T::uniform_locations() as GLint;
+                    *(&mut unit) = unit + $T::texture_units();
+                )*
+            }
+
+            unsafe fn bind_textures(&self, first: GLuint) {
+                let ($($t),*) = self;
+                let mut unit = first;
+                $(
+                    $t.bind_textures(unit);
+                    *(&mut unit) = unit + $T::texture_units();
+                )*
+            }
+
         }
 
     }
@@ -474,6 +884,27 @@ impl AttributeData<float> for f64 { fn format() -> FloatFormat { FloatFormat::Do
 
 impl AttributeData<double> for f64 { fn format() -> DoubleFormat {DoubleFormat}}
 
+impl AttributeData<float> for f16 { fn format() -> FloatFormat { FloatFormat::Float(FloatType::Half) }}
+impl AttributeData<float> for fixed { fn format() -> FloatFormat { FloatFormat::Fixed }}
+
+impl AttributeData<vec3> for Packed1010102 { fn format() -> VecFormat { VecFormat::UInt2_10_10_10Rev(false) }}
+impl AttributeData<vec4> for Packed1010102 { fn format() -> VecFormat { VecFormat::UInt2_10_10_10Rev(false) }}
+impl AttributeData<vec3> for IPacked1010102 { fn format() -> VecFormat { VecFormat::Int2_10_10_10Rev(false) }}
+impl AttributeData<vec4> for IPacked1010102 { fn format() -> VecFormat { VecFormat::Int2_10_10_10Rev(false) }}
+impl AttributeData<vec3> for Packed111110F { fn format() -> VecFormat { VecFormat::UInt10F_11F_11FRev(false) }}
+
+impl AttributeData<float> for Norm<u8> { fn format() -> FloatFormat { FloatFormat::FromInt(IntFormat::UByte, true) }}
+impl AttributeData<float> for Norm<u16> { fn format() -> FloatFormat { FloatFormat::FromInt(IntFormat::UShort, true) }}
+impl AttributeData<float> for Norm<u32> { fn format() -> FloatFormat { FloatFormat::FromInt(IntFormat::UInt, true) }}
+impl AttributeData<float> for SNorm<i8> { fn format() -> FloatFormat { FloatFormat::FromInt(IntFormat::Byte, true) }}
+impl AttributeData<float> for SNorm<i16> { fn format() -> FloatFormat { FloatFormat::FromInt(IntFormat::Short, true) }}
+impl AttributeData<float> for SNorm<i32> { fn format() -> FloatFormat { FloatFormat::FromInt(IntFormat::Int, true) }}
+
+impl AttributeData<vec3> for Norm<Packed1010102> { fn format() -> VecFormat { VecFormat::UInt2_10_10_10Rev(true) }}
+impl AttributeData<vec4> for Norm<Packed1010102> { fn format() -> VecFormat { VecFormat::UInt2_10_10_10Rev(true) }}
+impl AttributeData<vec3> for SNorm<IPacked1010102> { fn format() -> VecFormat { VecFormat::Int2_10_10_10Rev(true) }}
+impl AttributeData<vec4> for SNorm<IPacked1010102> { fn format() -> VecFormat { VecFormat::Int2_10_10_10Rev(true) }}
+
 impl_attr_data!(@IVec gl_bool bvec2 bvec3 bvec4);
 impl_attr_data!(@IVec uint uvec2 uvec3 uvec4);
 impl_attr_data!(@IVec int ivec2 ivec3 ivec4);
@@ -15,15 +15,39 @@ pub unsafe trait GLSLType: Sized + Copy + Debug {
     #[inline] fn uniform_locations() -> GLuint {1}
     #[inline] fn first_element_name(var: String) -> String { var }
 
+    ///
+    ///The type of this value in a program interface along with how many array elements it spans,
+    ///or `None` if it isn't reflected as a single variable (like structs)
+    ///
+    #[inline] fn data_type() -> Option<(DataType, usize)> { None }
+
     unsafe fn load_uniform(id: GLint, data: &Self) { Self::load_uniforms(id, from_ref(data)); }
     unsafe fn load_uniforms(id: GLint, data: &[Self]);
     unsafe fn get_uniform(p: GLuint, id:GLint) -> Self;
 
+    ///The number of texture units taken up by the samplers in this type
+    #[inline] fn texture_units() -> GLuint {0}
+
+    ///
+    ///Points each sampler in the uniform at `id` to its texture unit, counting up from `first`
+    ///
+    ///Since the unit of a sampler is stored in the program, this only needs to be done once per program
+    ///
+    #[inline] unsafe fn assign_texture_units(_id: GLint, _first: GLuint) {}
+
+    ///Binds the textures of each sampler in this value to their units, counting up from `first`
+    #[inline] unsafe fn bind_textures(&self, _first: GLuint) {}
+
     #[inline]
     unsafe fn bind_attribute(attr: GLuint, format: Self::AttributeFormat, stride: usize, offset: usize) {
         format.bind_attribute(attr, stride, offset);
     }
 
+    #[inline]
+    unsafe fn attribute_format(attr: GLuint, format: Self::AttributeFormat, relative_offset: usize) {
+        format.attribute_format(attr, relative_offset);
+    }
+
     #[inline]
     unsafe fn set_attribute(attr: GLuint, format: Self::AttributeFormat, data: *const GLvoid) {
         format.set_attribute(attr, data);
@@ -34,6 +58,32 @@ pub unsafe trait GLSLType: Sized + Copy + Debug {
 pub unsafe trait GLSLStruct { const SRC: &'static str; }
 pub unsafe trait GLSLFunction<ReturnType, Params> { const SRC: &'static str; }
 
+///
+///A public struct or function from [glsl!] that can be imported into other shaders with `use`
+///
+pub unsafe trait GLSLImport: 'static {
+    const SRC: &'static str;
+
+    ///Imports everything this item depends on into the given source
+    #[inline] fn import_dependencies(src: String) -> String { src }
+}
+
+///
+///Appends the source of an item and all of its dependencies to the given source, skipping any
+///that were already imported into it
+///
+pub fn import_source<T:GLSLImport>(mut src: String) -> String {
+    let marker = format!("\n//use {}\n", ::std::any::type_name::<T>());
+    if src.contains(&marker) { return src; }
+
+    //the marker goes first so that cyclic dependencies don't loop forever
+    src.push_str(&marker);
+    let mut src = T::import_dependencies(src);
+    src.push_str(T::SRC);
+    src.push('\n');
+    src
+}
+
 pub unsafe trait BlockLayout: Sized + Copy {}
 pub unsafe trait Layout<B:BlockLayout> {}
 pub unsafe trait AlignedVec4 {}
@@ -53,6 +103,7 @@ pub unsafe trait AttribFormat: Sized + Clone + Copy + PartialEq + Eq + Hash + De
     fn size(self) -> usize;
     fn attrib_count(self) -> usize {1}
     unsafe fn bind_attribute(self, attr_id: GLuint, stride: usize, offset: usize);
+    unsafe fn attribute_format(self, attr_id: GLuint, relative_offset: usize);
     unsafe fn set_attribute(self, attr_id: GLuint, data: *const GLvoid);
 }
 
@@ -68,3 +119,13 @@ impl<A:AttributeData<T>, T:GLSLType> AttributeValue<T> for A {
 pub trait GLSLSubroutine: Copy + Eq {
     fn function_name(&self) -> &'static ::std::ffi::CStr;
 }
+
+///
+///An image type from GLSL (`image2D`, etc), which gets bound to an image unit as an [Image] instead
+///of being loaded like other uniforms
+///
+pub unsafe trait GLSLImage {
+    ///The kind of internal format that textures bound to this image need
+    type Format: InternalFormat;
+    fn target() -> TextureTarget;
+}
@@ -0,0 +1,199 @@
+use super::*;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+#[derive(Default)]
+struct Includes {
+    files: BTreeMap<String, String>,
+    roots: Vec<PathBuf>
+}
+
+///
+///The virtual filesystem used to resolve `#include "name"` directives in shader source
+///
+///There is one for the whole process, shared by every [GLProvider] and reached with
+///[GLProvider::shader_fs]. Includes are looked up by name in the files added to it directly first,
+///then relative to each of the directory roots in the order they were added. All shaders created
+///with [Shader::create] (and so everything built on top of it) are preprocessed with it, and since
+///the preprocessing replaces the included code with `#line` directives around it, line numbers
+///in compilation errors still refer to the original files. The number of the source string in
+///those errors is the order each file was included in, starting from 1, and these are listed at
+///the end of the log.
+///
+#[derive(Default)]
+pub struct ShaderFS {
+    includes: RwLock<Includes>
+}
+
+pub(crate) static SHADER_FS: ShaderFS = ShaderFS::new();
+
+impl ShaderFS {
+
+    pub const fn new() -> Self {
+        ShaderFS { includes: RwLock::new(Includes { files: BTreeMap::new(), roots: Vec::new() }) }
+    }
+
+    ///Adds an in-memory file, replacing any previous one with the same name
+    pub fn add(&self, name: &str, src: &str) {
+        self.includes.write().unwrap().files.insert(name.to_owned(), src.to_owned());
+    }
+
+    ///Makes the source of a struct from [glsl!](crate::glsl) includable under the given name
+    #[inline] pub fn add_struct<S:GLSLStruct>(&self, name: &str) { self.add(name, S::SRC) }
+
+    ///Makes the source of a function from [glsl!](crate::glsl) includable under the given name
+    #[inline] pub fn add_function<F:GLSLFunction<R,P>, R, P>(&self, name: &str) { self.add(name, F::SRC) }
+
+    ///Adds a directory to search for includes that don't have an in-memory file
+    pub fn add_dir<P:AsRef<Path>>(&self, root: P) {
+        self.includes.write().unwrap().roots.push(root.as_ref().to_owned());
+    }
+
+    pub fn remove(&self, name: &str) -> Option<String> { self.includes.write().unwrap().files.remove(name) }
+
+    pub fn clear(&self) {
+        let mut includes = self.includes.write().unwrap();
+        includes.files.clear();
+        includes.roots.clear();
+    }
+
+    fn find(includes: &Includes, name: &str) -> Option<String> {
+        includes.files.get(name).cloned().or_else(
+            || includes.roots.iter().filter_map(|root| fs::read_to_string(root.join(name)).ok()).next()
+        )
+    }
+
+    ///
+    ///Resolves the includes of the given source, returning the result along with the names of
+    ///each included file by their source string number (minus one)
+    ///
+    pub fn preprocess(&self, src: &str) -> Result<(String, Vec<String>), String> {
+        //don't touch sources without any includes so their line numbers stay exactly the same
+        if !src.lines().any(|line| Self::include_name(line).is_some()) {
+            return Ok((src.to_owned(), Vec::new()));
+        }
+
+        let includes = self.includes.read().unwrap();
+        let mut out = String::with_capacity(src.len());
+        let mut names = Vec::new();
+        Self::resolve(&includes, src, 0, &mut vec!["<shader>".to_owned()], &mut out, &mut names)?;
+        Ok((out, names))
+    }
+
+    //gets the argument of the line if it is an include directive
+    fn include_name(line: &str) -> Option<Result<&str, ()>> {
+        let line = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?;
+        let line = line.trim();
+        if line.len() >= 2 && line.starts_with('"') && line.ends_with('"') {
+            Some(Ok(&line[1..line.len()-1]))
+        } else {
+            Some(Err(()))
+        }
+    }
+
+    fn resolve(
+        includes: &Includes, src: &str, source_num: usize,
+        stack: &mut Vec<String>, out: &mut String, names: &mut Vec<String>
+    ) -> Result<(), String> {
+
+        for (i, line) in src.lines().enumerate() {
+            match Self::include_name(line) {
+                None => { out.push_str(line); out.push('\n'); },
+                Some(Err(())) => return Err(format!(
+                    "{}({}) : error: malformed include (expected #include \"name\")", source_num, i+1
+                )),
+                Some(Ok(name)) => {
+                    if stack.iter().any(|s| s==name) {
+                        return Err(format!(
+                            "{}({}) : error: include cycle: {} -> {}", source_num, i+1, stack[1..].join(" -> "), name
+                        ));
+                    }
+
+                    let file = Self::find(includes, name).ok_or(
+                        format!("{}({}) : error: could not find include \"{}\"", source_num, i+1, name)
+                    )?;
+
+                    names.push(name.to_owned());
+                    let num = names.len();
+
+                    out.push_str(&format!("#line 1 {}\n", num));
+                    stack.push(name.to_owned());
+                    Self::resolve(includes, &file, num, stack, out, names)?;
+                    stack.pop();
+                    out.push_str(&format!("#line {} {}\n", i+2, source_num));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_includes() {
+        let fs = ShaderFS::new();
+        let src = "#version 450\nvoid main() {}";
+        assert_eq!(fs.preprocess(src).unwrap(), (src.to_owned(), Vec::new()));
+    }
+
+    #[test]
+    fn nested() {
+        let fs = ShaderFS::new();
+        fs.add("a.glsl", "float a;\n#include \"b.glsl\"\nfloat c;");
+        fs.add("b.glsl", "float b;");
+
+        let (out, names) = fs.preprocess("#version 450\n  # include \"a.glsl\"\nvoid main() {}").unwrap();
+        assert_eq!(names, vec!["a.glsl".to_owned(), "b.glsl".to_owned()]);
+        assert_eq!(
+            out,
+            "#version 450\n#line 1 1\nfloat a;\n#line 1 2\nfloat b;\n#line 3 1\nfloat c;\n#line 3 0\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn repeated() {
+        let fs = ShaderFS::new();
+        fs.add("a.glsl", "float a;");
+        let (out, names) = fs.preprocess("#include \"a.glsl\"\n#include \"a.glsl\"").unwrap();
+        assert_eq!(names, vec!["a.glsl".to_owned(); 2]);
+        assert_eq!(out, "#line 1 1\nfloat a;\n#line 2 0\n#line 1 2\nfloat a;\n#line 3 0\n");
+    }
+
+    #[test]
+    fn directories() {
+        let dir = ::std::env::temp_dir().join(format!("gl_struct_include_{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("d.glsl"), "float d;").unwrap();
+
+        let shader_fs = ShaderFS::new();
+        shader_fs.add_dir(&dir);
+        let res = shader_fs.preprocess("#include \"d.glsl\"");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(res.unwrap().0, "#line 1 1\nfloat d;\n#line 2 0\n");
+
+        //in-memory files come first
+        shader_fs.add("d.glsl", "float e;");
+        assert_eq!(shader_fs.preprocess("#include \"d.glsl\"").unwrap().0, "#line 1 1\nfloat e;\n#line 2 0\n");
+    }
+
+    #[test]
+    fn errors() {
+        let fs = ShaderFS::new();
+        fs.add("a.glsl", "#include \"b.glsl\"");
+        fs.add("b.glsl", "#include \"a.glsl\"");
+        fs.add("c.glsl", "\n#include <d.glsl>");
+
+        assert!(fs.preprocess("#include \"a.glsl\"").unwrap_err().contains("include cycle: a.glsl -> b.glsl -> a.glsl"));
+        assert_eq!(fs.preprocess("\n#include \"x.glsl\"").unwrap_err(), "0(2) : error: could not find include \"x.glsl\"");
+        assert!(fs.preprocess("#include \"c.glsl\"").unwrap_err().starts_with("1(2) : error: malformed include"));
+        assert!(fs.preprocess("#include").is_err());
+    }
+}
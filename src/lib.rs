@@ -25,6 +25,15 @@ use std::hash::Hash;
 pub use program::*;
 pub use glsl::*;
 pub use buffer::*;
+pub use vertex_array::*;
+pub use reloadable::*;
+pub use include::*;
+pub use reflect::*;
+pub use dynamic::*;
+pub use pipeline::*;
+pub use spirv::*;
+pub use texture::*;
+pub use compute::*;
 
 macro_rules! display_from_debug {
     ($name:ty) => {
@@ -226,8 +235,17 @@ macro_rules! check_loaded {
 pub mod glsl;
 pub mod program;
 pub mod buffer;
+pub mod vertex_array;
+pub mod mesh;
+pub mod reloadable;
+pub mod include;
+pub mod reflect;
+pub mod dynamic;
+pub mod pipeline;
+pub mod spirv;
 // pub mod buffer_new;
-// pub mod texture;
+pub mod texture;
+pub mod compute;
 
 pub trait Surface: {
     fn is_active(&self) -> bool;
@@ -252,11 +270,20 @@ impl GLProvider {
         }
     }
 
-    pub unsafe fn load<F: FnMut(&'static str) -> *const GLvoid>(proc_addr: F) -> GLProvider {
-        gl::load_with(proc_addr);
+    pub unsafe fn load<F: FnMut(&'static str) -> *const GLvoid>(mut proc_addr: F) -> GLProvider {
+        gl::load_with(&mut proc_addr);
+        spirv::load_with(&mut proc_addr);
+        compute::load_with(&mut proc_addr);
         GLProvider{ _private: () }
     }
 
+    ///
+    ///The files used to resolve `#include` directives in shaders
+    ///
+    ///This is the same for every provider, including the ones from [get_current](Self::get_current)
+    ///
+    #[inline] pub fn shader_fs(&self) -> &'static ShaderFS { &include::SHADER_FS }
+
     #[inline] pub fn upgrade(&self) -> Result<&GL2, GLError> {
         check_loaded!(
             GenBuffers, BindBuffer, DeleteBuffers, GetBufferParameteriv,
@@ -505,13 +532,35 @@ impl<R:Resource> BindingLocation<R> {
 ///and bind points
 ///
 pub struct Context {
+    restart_index: Option<GLuint>,
     _private: ::std::marker::PhantomData<*const ()>
 }
 
 impl Context {
     pub fn init(_gl: &GLProvider) -> Context {
-        Context { _private: ::std::marker::PhantomData }
+        Context { restart_index: None, _private: ::std::marker::PhantomData }
+    }
+
+    ///
+    ///Enables or disables primitive restart for indexed draws
+    ///
+    ///While enabled, any index equal to the given value ends the current primitive and starts a
+    ///new one
+    ///
+    pub fn primitive_restart(&mut self, index: Option<GLuint>) {
+        unsafe {
+            match index {
+                Some(i) => {
+                    gl::Enable(gl::PRIMITIVE_RESTART);
+                    gl::PrimitiveRestartIndex(i);
+                },
+                None => gl::Disable(gl::PRIMITIVE_RESTART)
+            }
+        }
+        self.restart_index = index;
     }
+
+    #[inline] pub fn restart_index(&self) -> Option<GLuint> { self.restart_index }
 }
 
 // impl !Send for Context {}
@@ -562,6 +611,8 @@ pub enum GLError {
     ShaderCompilation(GLenum, ShaderType, String),
     ProgramLinking(GLenum, String),
     ProgramValidation(GLenum, String),
+    PipelineValidation(GLuint, String),
+    InvalidSpirV(String),
     InvalidEnum(GLenum, String),
     InvalidOperation(String),
     InvalidBits(GLbitfield, String),
@@ -577,6 +628,8 @@ impl Debug for GLError {
             GLError::ShaderCompilation(id, ty, log) => write!(f, "{} #{} compilation error: {}", ty, id, log),
             GLError::ProgramLinking(id, log) => write!(f, "Program #{} link error with Program: {}", id, log),
             GLError::ProgramValidation(id, log) => write!(f, "Program #{} validation error: {}", id, log),
+            GLError::PipelineValidation(id, log) => write!(f, "Program pipeline #{} validation error: {}", id, log),
+            GLError::InvalidSpirV(msg) => write!(f, "Invalid SPIR-V module: {}", msg),
             GLError::InvalidEnum(id, ty) => write!(f, "Invalid enum: #{} is not a valid {}", id, ty),
             GLError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
             GLError::InvalidBits(id, ty) => write!(f, "Invalid bitfield: {:b} are not valid flags for {}", id, ty),
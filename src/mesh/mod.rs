@@ -0,0 +1,234 @@
+use super::*;
+use super::glsl_type::*;
+
+use std::io;
+use std::io::BufReader;
+use std::fs::File;
+use std::path::Path;
+
+pub use self::obj::*;
+pub use self::ply::*;
+
+mod obj;
+mod ply;
+
+///The kinds of per-vertex data that can be read from a mesh file
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Channel {
+    Position,
+    Normal,
+    UV,
+    Color
+}
+
+pub enum MeshError {
+    Io(io::Error),
+    Parse(usize, String),
+    Unsupported(String),
+    MissingChannel(Channel),
+    UnsupportedField(String)
+}
+
+display_from_debug!(MeshError);
+impl Debug for MeshError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            MeshError::Io(e) => write!(f, "Mesh IO error: {}", e),
+            MeshError::Parse(line, msg) => write!(f, "Mesh parse error on line {}: {}", line, msg),
+            MeshError::Unsupported(msg) => write!(f, "Unsupported mesh file: {}", msg),
+            MeshError::MissingChannel(c) => write!(f, "Mesh has no {:?} data", c),
+            MeshError::UnsupportedField(name) =>
+                write!(f, "Vertex field \"{}\" either doesn't exist or can't hold mesh data", name),
+        }
+    }
+}
+
+impl From<io::Error> for MeshError {
+    #[inline] fn from(e: io::Error) -> Self { MeshError::Io(e) }
+}
+
+///
+///A mesh loaded from a file with one entry per unique vertex in each of its channels
+///
+///Every channel is either empty (if the file didn't have that data) or the same length as
+///`positions`, and every three `indices` form a triangle. Files without any faces, like point
+///clouds, have no indices.
+///
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u32>
+}
+
+impl Mesh {
+
+    ///Opens and reads an OBJ or PLY file depending on its extension
+    pub fn open<P:AsRef<Path>>(path: P) -> Result<Self, MeshError> {
+        let path = path.as_ref();
+        let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        match ext.as_ref().map(|e| &e[..]) {
+            Some("obj") => Self::from_obj(BufReader::new(File::open(path)?)),
+            Some("ply") => Self::from_ply(BufReader::new(File::open(path)?)),
+            _ => Err(MeshError::Unsupported(format!("unknown mesh format for {}", path.display())))
+        }
+    }
+
+    #[inline] pub fn from_obj<R:io::BufRead>(reader: R) -> Result<Self, MeshError> { obj::read_obj(reader) }
+    #[inline] pub fn from_ply<R:io::BufRead>(reader: R) -> Result<Self, MeshError> { ply::read_ply(reader) }
+
+    #[inline] pub fn vertex_count(&self) -> usize { self.positions.len() }
+    #[inline] pub fn triangle_count(&self) -> usize { self.indices.len() / 3 }
+
+    pub fn has_channel(&self, c: Channel) -> bool {
+        match c {
+            Channel::Position => self.positions.len() > 0,
+            Channel::Normal => self.normals.len() > 0,
+            Channel::UV => self.uvs.len() > 0,
+            Channel::Color => self.colors.len() > 0,
+        }
+    }
+
+    ///The data of a channel for the vertex at the given index
+    pub fn channel(&self, c: Channel, i: usize) -> Option<&[f32]> {
+        match c {
+            Channel::Position => self.positions.get(i).map(|x| &x[..]),
+            Channel::Normal => self.normals.get(i).map(|x| &x[..]),
+            Channel::UV => self.uvs.get(i).map(|x| &x[..]),
+            Channel::Color => self.colors.get(i).map(|x| &x[..]),
+        }
+    }
+
+    ///
+    ///Interleaves the channels of this mesh into a list of vertices
+    ///
+    ///Each channel is written into the field with the paired name, for example:
+    ///```ignore
+    ///let verts: Vec<Vertex> = mesh.vertices(&[(Channel::Position, "pos"), (Channel::UV, "tex")])?;
+    ///```
+    ///Any fields that aren't mentioned are left at their default value.
+    ///
+    pub fn vertices<T:MeshVertex>(&self, mapping: &[(Channel, &str)]) -> Result<Vec<T>, MeshError> {
+        for &(c, _) in mapping {
+            if !self.has_channel(c) { return Err(MeshError::MissingChannel(c)); }
+        }
+
+        let mut vertices = vec![T::default(); self.vertex_count()];
+        for (i, v) in vertices.iter_mut().enumerate() {
+            for &(c, field) in mapping {
+                if !v.set_field(field, self.channel(c, i).unwrap()) {
+                    return Err(MeshError::UnsupportedField(field.to_string()));
+                }
+            }
+        }
+        Ok(vertices)
+    }
+
+    ///Interleaves the channels of this mesh and uploads them into a new vertex buffer
+    pub fn vertex_buffer<T:MeshVertex, A:ReadAccess+WriteAccess>(
+        &self, gl: &GLProvider, mapping: &[(Channel, &str)]
+    ) -> Result<Buffer<[T], A>, MeshError> {
+        Ok(Buffer::from_box(gl, self.vertices(mapping)?.into_boxed_slice()))
+    }
+
+    ///Uploads the triangle indices of this mesh into a new index buffer
+    pub fn index_buffer<A:ReadAccess+WriteAccess>(&self, gl: &GLProvider) -> Buffer<[u32], A> {
+        Buffer::from_box(gl, self.indices.clone().into_boxed_slice())
+    }
+
+}
+
+///
+///A vertex type that can have the channels of a [Mesh] written into its fields by name
+///
+///This is implemented automatically for all structs declared in [glsl!](crate::glsl)
+///
+pub trait MeshVertex: Copy + Default {
+    ///Writes the given data into the named field, returning false if there is no such field that can hold it
+    fn set_field(&mut self, field: &str, data: &[f32]) -> bool;
+}
+
+///A field type that can be filled with mesh channel data
+pub trait ChannelData {
+    ///Writes the given data into this value, returning false if this type can't hold mesh data
+    fn set_channel(&mut self, data: &[f32]) -> bool;
+}
+
+impl<T> ChannelData for T {
+    #[inline] default fn set_channel(&mut self, _data: &[f32]) -> bool { false }
+}
+
+///A single component of mesh data
+pub trait ChannelScalar: Copy {
+    fn from_channel(f: f32) -> Self;
+}
+
+macro_rules! impl_channel_scalar {
+    ($($ty:ty)*) => {$(
+        impl ChannelScalar for $ty { #[inline] fn from_channel(f: f32) -> Self { <$ty>::from(f) } }
+    )*}
+}
+
+impl_channel_scalar!(f32 f64 f16 fixed Norm<u8> Norm<u16> Norm<u32> SNorm<i8> SNorm<i16> SNorm<i32>);
+
+//any components missing from the data get filled in the same way OpenGL does with attributes,
+//ie (0,0,0,1)
+#[inline]
+fn fill_channel<T:ChannelScalar>(dest: &mut [T], data: &[f32]) -> bool {
+    for (i, x) in dest.iter_mut().enumerate() {
+        let f = match data.get(i) { Some(f) => *f, None => if i==3 {1.0} else {0.0} };
+        *x = T::from_channel(f);
+    }
+    true
+}
+
+impl<T:ChannelScalar> ChannelData for T {
+    #[inline] fn set_channel(&mut self, data: &[f32]) -> bool { fill_channel(::std::slice::from_mut(self), data) }
+}
+
+macro_rules! impl_channel_data {
+    (@arr $($n:literal)*) => {$(
+        impl<T:ChannelScalar> ChannelData for [T; $n] {
+            #[inline] fn set_channel(&mut self, data: &[f32]) -> bool { fill_channel(self, data) }
+        }
+    )*};
+    (@vec $($vec:ident)*) => {$(
+        impl ChannelData for $vec {
+            #[inline] fn set_channel(&mut self, data: &[f32]) -> bool { fill_channel(&mut self.value, data) }
+        }
+    )*};
+}
+
+impl_channel_data!(@arr 1 2 3 4);
+impl_channel_data!(@vec vec2 vec3 vec4 dvec2 dvec3 dvec4);
+
+impl ChannelData for Norm<Packed1010102> {
+    fn set_channel(&mut self, data: &[f32]) -> bool {
+        let mut v = [0.0f32; 4];
+        fill_channel(&mut v, data);
+        let n = |f: f32, max: f32| (f.max(0.0).min(1.0) * max).round() as u32;
+        *self = Norm(Packed1010102::new(n(v[0], 1023.0), n(v[1], 1023.0), n(v[2], 1023.0), n(v[3], 3.0)));
+        true
+    }
+}
+
+impl ChannelData for SNorm<IPacked1010102> {
+    fn set_channel(&mut self, data: &[f32]) -> bool {
+        let mut v = [0.0f32; 4];
+        fill_channel(&mut v, data);
+        let n = |f: f32, max: f32| (f.max(-1.0).min(1.0) * max).round() as i32;
+        *self = SNorm(IPacked1010102::new(n(v[0], 511.0), n(v[1], 511.0), n(v[2], 511.0), n(v[3], 1.0)));
+        true
+    }
+}
+
+impl ChannelData for Packed111110F {
+    fn set_channel(&mut self, data: &[f32]) -> bool {
+        let mut v = [0.0f32; 3];
+        fill_channel(&mut v, data);
+        *self = Packed111110F::new(v[0], v[1], v[2]);
+        true
+    }
+}
@@ -0,0 +1,180 @@
+use super::*;
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::str::FromStr;
+
+fn parse_floats<'a, I:Iterator<Item=&'a str>>(line: usize, tokens: I) -> Result<Vec<f32>, MeshError> {
+    tokens.map(
+        |t| f32::from_str(t).map_err(|_| MeshError::Parse(line, format!("invalid number \"{}\"", t)))
+    ).collect()
+}
+
+//converts a one-based and possibly negative OBJ index into a zero-based one
+fn parse_index(line: usize, token: &str, count: usize) -> Result<usize, MeshError> {
+    let err = || MeshError::Parse(line, format!("invalid index \"{}\"", token));
+    let i = isize::from_str(token).map_err(|_| err())?;
+    let i = if i < 0 { count as isize + i } else { i - 1 };
+    if i < 0 || i as usize >= count { Err(err()) } else { Ok(i as usize) }
+}
+
+///
+///Reads a Wavefront OBJ file
+///
+///Only the geometry is read, so materials, groups, smoothing, lines, and points are all ignored.
+///Polygons are triangulated as fans and any per-vertex colors following the positions are read
+///into the color channel (with the vertices that have none being white). Positions that no face
+///uses, like those of point clouds, are still kept as vertices of their own.
+///
+pub fn read_obj<R:BufRead>(reader: R) -> Result<Mesh, MeshError> {
+
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+
+    let mut mesh = Mesh::default();
+    let (mut has_uv, mut has_normal) = (false, false);
+    let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let n = n + 1;
+        let line = match line.find('#') { Some(i) => &line[..i], None => &line[..] };
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                let v = parse_floats(n, tokens)?;
+                let color = match v.len() {
+                    3 | 4 => None,
+                    6 | 7 => Some([v[3], v[4], v[5], v.get(6).cloned().unwrap_or(1.0)]),
+                    _ => return Err(MeshError::Parse(n, "expected 3 or 4 position components".to_string()))
+                };
+                positions.push([v[0], v[1], v[2]]);
+
+                //once any position has a color, they all get one so that the two stay lined up
+                if color.is_some() || !colors.is_empty() {
+                    colors.resize(positions.len() - 1, [1.0; 4]);
+                    colors.push(color.unwrap_or([1.0; 4]));
+                }
+            },
+            Some("vt") => {
+                let v = parse_floats(n, tokens)?;
+                if v.len() < 1 || v.len() > 3 {
+                    return Err(MeshError::Parse(n, "expected 1 to 3 texture coordinates".to_string()));
+                }
+                uvs.push([v[0], v.get(1).cloned().unwrap_or(0.0)]);
+            },
+            Some("vn") => {
+                let v = parse_floats(n, tokens)?;
+                if v.len() != 3 { return Err(MeshError::Parse(n, "expected 3 normal components".to_string())); }
+                normals.push([v[0], v[1], v[2]]);
+            },
+            Some("f") => {
+                let mut face = Vec::new();
+                for t in tokens {
+                    let mut parts = t.split('/');
+                    let v = parse_index(n, parts.next().unwrap(), positions.len())?;
+                    let vt = match parts.next() {
+                        Some("") | None => None,
+                        Some(i) => Some(parse_index(n, i, uvs.len())?)
+                    };
+                    let vn = match parts.next() {
+                        Some("") | None => None,
+                        Some(i) => Some(parse_index(n, i, normals.len())?)
+                    };
+
+                    has_uv |= vt.is_some();
+                    has_normal |= vn.is_some();
+
+                    //each unique combination of position, uv, and normal becomes a new vertex
+                    let next = mesh.positions.len() as u32;
+                    let id = *vertices.entry((v, vt, vn)).or_insert(next);
+                    if id == next {
+                        mesh.positions.push(positions[v]);
+                        mesh.colors.push(colors.get(v).cloned().unwrap_or([1.0; 4]));
+                        mesh.uvs.push(vt.map_or([0.0; 2], |i| uvs[i]));
+                        mesh.normals.push(vn.map_or([0.0; 3], |i| normals[i]));
+                    }
+                    face.push(id);
+                }
+
+                if face.len() < 3 { return Err(MeshError::Parse(n, "faces need at least 3 vertices".to_string())); }
+                for i in 1..face.len()-1 {
+                    mesh.indices.extend_from_slice(&[face[0], face[i], face[i+1]]);
+                }
+            },
+            _ => ()
+        }
+    }
+
+    //points that aren't part of any face still need to be in the mesh
+    let mut used = vec![false; positions.len()];
+    for &(v, _, _) in vertices.keys() { used[v] = true; }
+    for v in (0..positions.len()).filter(|&v| !used[v]) {
+        mesh.positions.push(positions[v]);
+        mesh.colors.push(colors.get(v).cloned().unwrap_or([1.0; 4]));
+        mesh.uvs.push([0.0; 2]);
+        mesh.normals.push([0.0; 3]);
+    }
+
+    if colors.len() == 0 { mesh.colors.clear(); }
+    if !has_uv { mesh.uvs.clear(); }
+    if !has_normal { mesh.normals.clear(); }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(src: &str) -> Result<Mesh, MeshError> { read_obj(src.as_bytes()) }
+
+    #[test]
+    fn quad() {
+        let mesh = read("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1 4//1\n").unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]; 4]);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert!(mesh.uvs.is_empty() && mesh.colors.is_empty());
+    }
+
+    #[test]
+    fn shared_and_negative_indices() {
+        let mesh = read("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nf 1/1 2/1 3/1\nf -3/-1 -1/1 -2/1 # comment\n").unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.uvs.len(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 1, 3, 2]);
+    }
+
+    #[test]
+    fn point_cloud() {
+        let mesh = read("v 0 0 0\nv 1 0 0\nv 0 1 0\n").unwrap();
+        assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert!(mesh.indices.is_empty());
+    }
+
+    #[test]
+    fn unreferenced_positions() {
+        let mesh = read("v 0 0 0\nv 5 5 5\nv 1 0 0\nv 0 1 0\nf 1 3 4\n").unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.positions[3], [5.0, 5.0, 5.0]);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn partial_colors() {
+        let mesh = read("v 0 0 0\nv 1 0 0 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        assert_eq!(mesh.colors, vec![[1.0; 4], [1.0, 0.0, 0.0, 1.0], [1.0; 4]]);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(read("v 0 0\n").is_err());
+        assert!(read("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n").is_err());
+        assert!(read("v 0 0 0\nv 1 0 0\nf 1 2\n").is_err());
+        assert!(read("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 0\n").is_err());
+        assert!(read("v 0 x 0\n").is_err());
+    }
+}
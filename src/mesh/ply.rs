@@ -0,0 +1,382 @@
+use super::*;
+
+use std::io::{BufRead, Lines};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PlyType { Char, UChar, Short, UShort, Int, UInt, Float, Double }
+
+impl PlyType {
+    fn parse(line: usize, name: Option<&str>) -> Result<Self, MeshError> {
+        match name {
+            Some("char") | Some("int8") => Ok(PlyType::Char),
+            Some("uchar") | Some("uint8") => Ok(PlyType::UChar),
+            Some("short") | Some("int16") => Ok(PlyType::Short),
+            Some("ushort") | Some("uint16") => Ok(PlyType::UShort),
+            Some("int") | Some("int32") => Ok(PlyType::Int),
+            Some("uint") | Some("uint32") => Ok(PlyType::UInt),
+            Some("float") | Some("float32") => Ok(PlyType::Float),
+            Some("double") | Some("float64") => Ok(PlyType::Double),
+            Some(name) => Err(MeshError::Parse(line, format!("unknown property type \"{}\"", name))),
+            None => Err(MeshError::Parse(line, "missing property type".to_string()))
+        }
+    }
+
+    //the value that maps to 1.0 for colors stored as integers
+    fn max_value(self) -> f32 {
+        match self {
+            PlyType::Char => ::std::i8::MAX as f32,
+            PlyType::UChar => ::std::u8::MAX as f32,
+            PlyType::Short => ::std::i16::MAX as f32,
+            PlyType::UShort => ::std::u16::MAX as f32,
+            PlyType::Int => ::std::i32::MAX as f32,
+            PlyType::UInt => ::std::u32::MAX as f32,
+            PlyType::Float | PlyType::Double => 1.0,
+        }
+    }
+}
+
+struct PlyProperty {
+    name: String,
+    ty: PlyType,
+    list: Option<PlyType>
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>
+}
+
+enum PlyBody<R:BufRead> {
+    Ascii { lines: Lines<R>, line: usize, tokens: Vec<String> },
+    Binary { reader: R, big_endian: bool, line: usize }
+}
+
+impl<R:BufRead> PlyBody<R> {
+
+    //the line to report errors on, which for binary files is the end of the header
+    fn line(&self) -> usize {
+        match self { PlyBody::Ascii{line, ..} => *line, PlyBody::Binary{line, ..} => *line }
+    }
+
+    //ascii files have each element on its own line
+    fn next_element(&mut self) -> Result<(), MeshError> {
+        if let PlyBody::Ascii{lines, line, tokens} = self {
+            loop {
+                *line += 1;
+                let next = lines.next().ok_or(MeshError::Parse(*line, "unexpected end of file".to_string()))??;
+                *tokens = next.split_whitespace().rev().map(|t| t.to_string()).collect();
+                if tokens.len() > 0 { break; }
+            }
+        }
+        Ok(())
+    }
+
+    //reads a list count or vertex index, neither of which can be negative or fractional
+    fn read_index(&mut self, ty: PlyType) -> Result<usize, MeshError> {
+        let x = self.read(ty)?;
+        if x < 0.0 || x.fract() != 0.0 {
+            Err(MeshError::Parse(self.line(), format!("invalid count or index {}", x)))
+        } else {
+            Ok(x as usize)
+        }
+    }
+
+    fn read(&mut self, ty: PlyType) -> Result<f64, MeshError> {
+        match self {
+            PlyBody::Ascii{line, tokens, ..} => {
+                let t = tokens.pop().ok_or(MeshError::Parse(*line, "too few values".to_string()))?;
+                let x = match ty {
+                    PlyType::Float | PlyType::Double => f64::from_str(&t).ok(),
+                    _ => i64::from_str(&t).ok().map(|i| i as f64)
+                };
+                x.ok_or(MeshError::Parse(*line, format!("invalid {:?} \"{}\"", ty, t)))
+            },
+            PlyBody::Binary{reader, big_endian, ..} => {
+                macro_rules! read {
+                    ($prim:ident) => {{
+                        let mut bytes = [0u8; ::std::mem::size_of::<$prim>()];
+                        reader.read_exact(&mut bytes)?;
+                        let x = if *big_endian { $prim::from_be_bytes(bytes) } else { $prim::from_le_bytes(bytes) };
+                        x as f64
+                    }}
+                }
+
+                Ok(match ty {
+                    PlyType::Char => read!(i8),
+                    PlyType::UChar => read!(u8),
+                    PlyType::Short => read!(i16),
+                    PlyType::UShort => read!(u16),
+                    PlyType::Int => read!(i32),
+                    PlyType::UInt => read!(u32),
+                    PlyType::Float => read!(f32),
+                    PlyType::Double => read!(f64),
+                })
+            }
+        }
+    }
+}
+
+//maps the common names of vertex properties onto a channel and component
+fn vertex_property(name: &str) -> Option<(Channel, usize)> {
+    match name {
+        "x" => Some((Channel::Position, 0)),
+        "y" => Some((Channel::Position, 1)),
+        "z" => Some((Channel::Position, 2)),
+        "nx" => Some((Channel::Normal, 0)),
+        "ny" => Some((Channel::Normal, 1)),
+        "nz" => Some((Channel::Normal, 2)),
+        "u" | "s" | "texture_u" | "texture_s" => Some((Channel::UV, 0)),
+        "v" | "t" | "texture_v" | "texture_t" => Some((Channel::UV, 1)),
+        "red" | "diffuse_red" => Some((Channel::Color, 0)),
+        "green" | "diffuse_green" => Some((Channel::Color, 1)),
+        "blue" | "diffuse_blue" => Some((Channel::Color, 2)),
+        "alpha" | "diffuse_alpha" => Some((Channel::Color, 3)),
+        _ => None
+    }
+}
+
+fn read_header<R:BufRead>(reader: &mut R) -> Result<(Option<bool>, Vec<PlyElement>, usize), MeshError> {
+
+    let mut line = 0;
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+
+    loop {
+        let mut s = String::new();
+        if reader.read_line(&mut s)? == 0 {
+            return Err(MeshError::Parse(line, "missing end_header".to_string()));
+        }
+        line += 1;
+
+        let mut tokens = s.split_whitespace();
+        let parse_count = |t: Option<&str>| t.and_then(|t| usize::from_str(t).ok()).ok_or(
+            MeshError::Parse(line, "invalid element count".to_string())
+        );
+
+        match (line, tokens.next()) {
+            (1, Some("ply")) => (),
+            (1, _) => return Err(MeshError::Unsupported("missing PLY magic number".to_string())),
+            (_, Some("format")) => {
+                format = match tokens.next() {
+                    Some("ascii") => Some(None),
+                    Some("binary_little_endian") => Some(Some(false)),
+                    Some("binary_big_endian") => Some(Some(true)),
+                    f => return Err(MeshError::Unsupported(format!("PLY format {:?}", f)))
+                };
+            },
+            (_, Some("element")) => {
+                let name = tokens.next().ok_or(MeshError::Parse(line, "missing element name".to_string()))?;
+                elements.push(PlyElement{
+                    name: name.to_string(), count: parse_count(tokens.next())?, properties: Vec::new()
+                });
+            },
+            (_, Some("property")) => {
+                let ty = tokens.next();
+                let (list, ty) = match ty {
+                    Some("list") => (Some(PlyType::parse(line, tokens.next())?), PlyType::parse(line, tokens.next())?),
+                    _ => (None, PlyType::parse(line, ty)?)
+                };
+                let name = tokens.next().ok_or(MeshError::Parse(line, "missing property name".to_string()))?;
+                let element = elements.last_mut().ok_or(
+                    MeshError::Parse(line, "property declared outside of an element".to_string())
+                )?;
+                element.properties.push(PlyProperty{name: name.to_string(), ty, list});
+            },
+            (_, Some("end_header")) => break,
+            (_, Some("comment")) | (_, Some("obj_info")) | (_, None) => (),
+            (_, Some(kw)) => return Err(MeshError::Parse(line, format!("unknown header keyword \"{}\"", kw)))
+        }
+    }
+
+    match format {
+        Some(big_endian) => Ok((big_endian, elements, line)),
+        None => Err(MeshError::Parse(line, "missing format".to_string()))
+    }
+}
+
+///
+///Reads an ASCII or binary PLY file
+///
+///Only the `vertex` and `face` elements are read, and vertex properties are assigned to channels
+///by their usual names (ie `x`, `nx`, `u` or `s`, `red`, etc). Integer colors are normalized to `[0,1]`
+///and polygons are triangulated as fans.
+///
+pub fn read_ply<R:BufRead>(mut reader: R) -> Result<Mesh, MeshError> {
+
+    let (format, elements, line) = read_header(&mut reader)?;
+    let mut body = match format {
+        None => PlyBody::Ascii { lines: reader.lines(), line, tokens: Vec::new() },
+        Some(big_endian) => PlyBody::Binary { reader, big_endian, line }
+    };
+
+    let mut mesh = Mesh::default();
+
+    for element in elements.iter() {
+
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+
+        //there's nothing to read, and without properties, nothing to stop a bogus count either
+        if element.properties.is_empty() { continue; }
+
+        let channels: Vec<_> = element.properties.iter().map(
+            |p| if is_vertex && p.list.is_none() { vertex_property(&p.name) } else { None }
+        ).collect();
+
+        let has = |c: Channel| is_vertex && channels.iter().any(|x| x.map(|x| x.0) == Some(c));
+        let (has_normal, has_uv, has_color) = (has(Channel::Normal), has(Channel::UV), has(Channel::Color));
+
+        //the channels grow as the vertices are read so that a malformed count can't allocate everything up front
+        for i in 0..element.count {
+            body.next_element()?;
+
+            if is_vertex {
+                mesh.positions.push([0.0; 3]);
+                if has_normal { mesh.normals.push([0.0; 3]); }
+                if has_uv { mesh.uvs.push([0.0; 2]); }
+                if has_color { mesh.colors.push([0.0, 0.0, 0.0, 1.0]); }
+            }
+
+            for (p, channel) in element.properties.iter().zip(channels.iter()) {
+                match p.list {
+                    Some(count_ty) => {
+                        let count = body.read_index(count_ty)?;
+
+                        if is_face && (p.name == "vertex_indices" || p.name == "vertex_index") {
+                            let mut face = Vec::new();
+                            for _ in 0..count {
+                                let v = body.read_index(p.ty)?;
+                                if v >= mesh.positions.len() {
+                                    return Err(MeshError::Parse(body.line(), "vertex index out of bounds".to_string()));
+                                }
+                                face.push(v as u32);
+                            }
+
+                            if count < 3 {
+                                return Err(MeshError::Parse(body.line(), "faces need at least 3 vertices".to_string()));
+                            }
+                            for k in 1..count-1 {
+                                mesh.indices.extend_from_slice(&[face[0], face[k], face[k+1]]);
+                            }
+                        } else {
+                            for _ in 0..count { body.read(p.ty)?; }
+                        }
+                    },
+                    None => {
+                        let x = body.read(p.ty)? as f32;
+                        match *channel {
+                            Some((Channel::Position, k)) => mesh.positions[i][k] = x,
+                            Some((Channel::Normal, k)) => mesh.normals[i][k] = x,
+                            Some((Channel::UV, k)) => mesh.uvs[i][k] = x,
+                            Some((Channel::Color, k)) => mesh.colors[i][k] = x / p.ty.max_value(),
+                            None => ()
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(src: &[u8]) -> Result<Mesh, MeshError> { read_ply(src) }
+
+    const ASCII: &str = "ply
+format ascii 1.0
+comment a colored quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+
+    #[test]
+    fn ascii() {
+        let mesh = read(ASCII.as_bytes()).unwrap();
+        assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(mesh.colors[0], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert!(mesh.normals.is_empty() && mesh.uvs.is_empty());
+    }
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut data = format!(
+            "ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+             element face 1\nproperty list uchar uint vertex_indices\nend_header\n", format
+        ).into_bytes();
+
+        for v in [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]].iter() {
+            for x in v.iter() {
+                data.extend_from_slice(&if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+            }
+        }
+        data.push(3);
+        for i in 0..3u32 {
+            data.extend_from_slice(&if big_endian { i.to_be_bytes() } else { i.to_le_bytes() });
+        }
+        data
+    }
+
+    #[test]
+    fn binary_both_endians() {
+        for &big_endian in [false, true].iter() {
+            let mesh = read(&binary(big_endian)).unwrap();
+            assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+            assert_eq!(mesh.indices, vec![0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let data = binary(false);
+        assert!(read(&data[..data.len()-2]).is_err());
+    }
+
+    #[test]
+    fn bogus_counts() {
+        //neither of these should try to allocate anything close to the declared counts
+        let huge_vertices = ASCII.replace("element vertex 4", "element vertex 18446744073709551615");
+        assert!(read(huge_vertices.as_bytes()).is_err());
+
+        let huge_list = ASCII.replace("4 0 1 2 3", "4000000000 0 1 2 3");
+        assert!(read(huge_list.as_bytes()).is_err());
+
+        let empty_element = "ply\nformat binary_little_endian 1.0\nelement junk 18446744073709551615\nend_header\n";
+        assert_eq!(read(empty_element.as_bytes()).unwrap(), Mesh::default());
+    }
+
+    #[test]
+    fn invalid_indices() {
+        assert!(read(ASCII.replace("4 0 1 2 3", "4 0 1 -2 3").as_bytes()).is_err());
+        assert!(read(ASCII.replace("4 0 1 2 3", "4 0 1 2 4").as_bytes()).is_err());
+        assert!(read(ASCII.replace("4 0 1 2 3", "-1 0 1 2 3").as_bytes()).is_err());
+        assert!(read(ASCII.replace("4 0 1 2 3", "2 0 1").as_bytes()).is_err());
+    }
+
+    #[test]
+    fn bad_headers() {
+        assert!(read(b"plx\nformat ascii 1.0\nend_header\n").is_err());
+        assert!(read(b"ply\nend_header\n").is_err());
+        assert!(read(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n").is_err());
+        assert!(read(b"ply\nformat ascii 1.0\nelement vertex 1\n").is_err());
+    }
+}
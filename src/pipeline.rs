@@ -0,0 +1,305 @@
+use super::*;
+
+use std::collections::HashMap;
+
+use dynamic::{BlockBinding, check_attributes, check_block, bind_blocks, unbind_blocks, not_found};
+
+///
+///A separable program that can be used for some of the stages of a [ProgramPipeline]
+///
+pub trait PipelineStage {
+    fn program_id(&self) -> &ProgramID;
+
+    ///
+    ///Loads any uniforms that have changed into the program
+    ///
+    ///This is called with the program set as the active program of the pipeline, so uniforms
+    ///can be loaded with `glUniform*` as usual
+    ///
+    unsafe fn load_uniforms(&self) {}
+}
+
+impl PipelineStage for ProgramID {
+    #[inline] fn program_id(&self) -> &ProgramID { self }
+}
+
+impl PipelineStage for DynamicProgram {
+    #[inline] fn program_id(&self) -> &ProgramID { self.program() }
+    #[inline] unsafe fn load_uniforms(&self) { DynamicProgram::load_uniforms(self) }
+}
+
+//the stages that can be drawn with, in the order the vertices go through them
+const DRAW_STAGES: [ShaderType; 5] = [
+    ShaderType::Vertex, ShaderType::TessControl, ShaderType::TessEval, ShaderType::Geometry, ShaderType::Fragment
+];
+
+fn stage_bit(stage: ShaderType) -> GLbitfield {
+    match stage {
+        ShaderType::Vertex => gl::VERTEX_SHADER_BIT,
+        ShaderType::TessControl => gl::TESS_CONTROL_SHADER_BIT,
+        ShaderType::TessEval => gl::TESS_EVALUATION_SHADER_BIT,
+        ShaderType::Geometry => gl::GEOMETRY_SHADER_BIT,
+        ShaderType::Fragment => gl::FRAGMENT_SHADER_BIT,
+        ShaderType::Compute => gl::COMPUTE_SHADER_BIT,
+    }
+}
+
+struct PipelineProgram {
+    id: GLuint,
+    stages: Vec<ShaderType>,
+    interface: ProgramInterface,
+    patch_vertices: Option<GLuint>,
+    geometry_input: Option<GeometryInput>
+}
+
+///
+///A program pipeline object, which combines the stages of separate separable programs
+///
+///This way, each vertex program can be paired with any fragment program (for example) without
+///having to link a program for every combination. Since the programs each have their own
+///interface, the pipeline keeps a [reflection](ProgramID::reflect) of each one in order to check
+///the outputs of each stage against the inputs of the next in [validate](Self::validate), and to
+///look up the attributes and blocks passed to [draw](Self::draw) by name.
+///
+///Note that each program keeps its own block bindings, so when mixing programs from
+///[glsl!](crate::glsl), blocks should be given distinct `layout(binding = N)` qualifiers.
+///
+///Requires GL 4.1 or `ARB_separate_shader_objects`, along with GL 4.3 or `ARB_program_interface_query`
+///
+pub struct ProgramPipeline {
+    id: GLuint,
+    programs: Vec<PipelineProgram>,
+    attribute: Option<RawVertexArray>
+}
+
+impl ProgramPipeline {
+
+    pub fn new(gl: &GLProvider) -> Self {
+        let mut id = 0;
+        unsafe { gl::GenProgramPipelines(1, &mut id as *mut GLuint); }
+        ProgramPipeline {
+            id: id,
+            programs: Vec::new(),
+            attribute: if gl::GenVertexArrays::is_loaded() { Some(RawVertexArray::gen(gl)) } else { None }
+        }
+    }
+
+    #[inline] pub fn id(&self) -> GLuint { self.id }
+
+    ///Gets the id of the program used for the given stage, if any
+    pub fn stage(&self, stage: ShaderType) -> Option<GLuint> {
+        self.program(stage).map(|p| p.id)
+    }
+
+    ///
+    ///Uses every stage of the given program in this pipeline, replacing whichever programs
+    ///were used for those stages before
+    ///
+    pub fn use_program<P:PipelineStage+?Sized>(&mut self, program: &P) -> Result<(), GLError> {
+        let program = program.program_id();
+        if !program.is_separable() {
+            return Err(GLError::InvalidOperation(
+                format!("Program #{} was not made separable and can't be used in a pipeline", program.id())
+            ));
+        }
+
+        let stages = program.stages().to_vec();
+        unsafe {
+            let bits = stages.iter().fold(0, |bits, s| bits | stage_bit(*s));
+            gl::UseProgramStages(self.id, bits, program.id());
+        }
+
+        self.remove(&stages);
+        self.programs.push(PipelineProgram {
+            id: program.id(),
+            stages,
+            interface: program.reflect(),
+            patch_vertices: program.patch_vertices(),
+            geometry_input: program.geometry_input()
+        });
+        Ok(())
+    }
+
+    ///Removes the programs used for the given stages
+    pub fn clear_stages(&mut self, stages: &[ShaderType]) {
+        unsafe {
+            let bits = stages.iter().fold(0, |bits, s| bits | stage_bit(*s));
+            gl::UseProgramStages(self.id, bits, 0);
+        }
+        self.remove(stages);
+    }
+
+    fn remove(&mut self, stages: &[ShaderType]) {
+        for p in self.programs.iter_mut() { p.stages.retain(|s| !stages.contains(s)); }
+        self.programs.retain(|p| p.stages.len() > 0);
+    }
+
+    fn program(&self, stage: ShaderType) -> Option<&PipelineProgram> {
+        self.programs.iter().find(|p| p.stages.contains(&stage))
+    }
+
+    ///
+    ///Checks that the stages of this pipeline can be used together
+    ///
+    ///Along with the driver's own validation, this checks that every input of each program is
+    ///written by the stage before it with the same type, and that no two programs use the same
+    ///binding index for different blocks.
+    ///
+    pub fn validate(&self) -> Result<(), GLError> {
+        let err = |msg: String| GLError::PipelineValidation(self.id, msg);
+
+        //match up each program's inputs with the outputs of the program before it
+        let mut prev: Option<(ShaderType, &PipelineProgram)> = None;
+        for &stage in DRAW_STAGES.iter() {
+            let program = match self.program(stage) { Some(p) => p, None => continue };
+
+            if let Some((prev_stage, prev_program)) = prev {
+                if prev_program.id != program.id {
+                    for input in program.interface.attributes.iter().filter(|i| !i.name.starts_with("gl_")) {
+                        let output = prev_program.interface.output(&input.name).or_else(
+                            || prev_program.interface.outputs.iter().find(|o| input.location >= 0 && o.location == input.location)
+                        ).ok_or_else(|| err(format!(
+                            "The {} input \"{}\" isn't written by the {}", stage, input.name, prev_stage
+                        )))?;
+
+                        if output.ty != input.ty {
+                            return Err(err(format!(
+                                "The {} input \"{}\" doesn't match the type of the {} output \"{}\"",
+                                stage, input.name, prev_stage, output.name
+                            )));
+                        }
+                    }
+                }
+            }
+
+            prev = Some((stage, program));
+        }
+
+        //blocks from different programs can't share a binding index
+        let mut bindings: HashMap<(GLenum, GLuint), &str> = HashMap::new();
+        for program in self.programs.iter() {
+            let blocks = program.interface.uniform_blocks.iter().map(|b| (gl::UNIFORM_BUFFER, b)).chain(
+                program.interface.storage_blocks.iter().map(|b| (gl::SHADER_STORAGE_BUFFER, b))
+            );
+            for (target, block) in blocks {
+                let name = bindings.entry((target, block.binding)).or_insert(&block.name);
+                if *name != block.name {
+                    return Err(err(format!(
+                        "The blocks \"{}\" and \"{}\" both use binding {}", name, block.name, block.binding
+                    )));
+                }
+            }
+        }
+
+        unsafe {
+            gl::ValidateProgramPipeline(self.id);
+
+            let mut status = 0;
+            gl::GetProgramPipelineiv(self.id, gl::VALIDATE_STATUS, &mut status as *mut GLint);
+            if status == gl::FALSE as GLint { return Err(err(self.info_log())); }
+        }
+
+        Ok(())
+    }
+
+    fn info_log(&self) -> String {
+        unsafe {
+            let mut len = 0;
+            gl::GetProgramPipelineiv(self.id, gl::INFO_LOG_LENGTH, &mut len as *mut GLint);
+            if len > 0 {
+                let mut log: Vec<u8> = vec![0; len as usize];
+                gl::GetProgramPipelineInfoLog(self.id, len, ::std::ptr::null_mut(), log.as_mut_ptr() as *mut GLchar);
+                String::from_utf8_lossy(&log).trim_end_matches('\0').to_owned()
+            } else {
+                "".to_owned()
+            }
+        }
+    }
+
+    fn check_blocks<'b>(&self, blocks: &HashMap<&str, &'b dyn AnyBuffer>) -> Result<Vec<BlockBinding<'b>>, GLError> {
+        let mut bindings = Vec::with_capacity(blocks.len());
+        for (name, &buffer) in blocks.iter() {
+            //the same block can be in more than one program
+            let start = bindings.len();
+            for program in self.programs.iter() {
+                if let Some((target, binding)) = check_block(&program.interface, name, buffer)? {
+                    bindings.push((target, binding, buffer));
+                }
+            }
+            if bindings.len() == start { return Err(not_found("block", name)); }
+        }
+        Ok(bindings)
+    }
+
+    ///
+    ///Binds the pipeline and draws `count` vertices with the given attributes and blocks, each keyed
+    ///by their name in the shaders
+    ///
+    ///Any changed uniforms of the given programs are loaded first. Since the pipeline only refers to
+    ///its programs by id, these are passed in on each draw so that their uniforms can still be
+    ///changed in between.
+    ///
+    pub fn draw(
+        &self,
+        _context: &mut Context,
+        mode: DrawMode,
+        count: usize,
+        programs: &[&dyn PipelineStage],
+        attributes: &HashMap<&str, &dyn AnyAttribute>,
+        blocks: &HashMap<&str, &dyn AnyBuffer>
+    ) -> Result<(), GLError> {
+
+        for p in programs.iter() {
+            let id = p.program_id().id();
+            if !self.programs.iter().any(|program| program.id == id) {
+                return Err(GLError::InvalidOperation(format!("Program #{} isn't used in pipeline #{}", id, self.id)));
+            }
+        }
+
+        let mode = mode.with_patch_vertices(self.program(ShaderType::TessControl).and_then(|p| p.patch_vertices));
+        mode.validate_stages(
+            self.program(ShaderType::TessEval).is_some(),
+            self.program(ShaderType::Geometry).and_then(|p| p.geometry_input)
+        )?;
+
+        let attributes = match self.program(ShaderType::Vertex) {
+            Some(vertex) => check_attributes(&vertex.interface, attributes, count)?,
+            None => match attributes.keys().next() {
+                Some(name) => return Err(not_found("attribute", name)),
+                None => Vec::new()
+            }
+        };
+        let blocks = self.check_blocks(blocks)?;
+
+        unsafe {
+            //a program from glUseProgram takes priority over the pipeline
+            ProgramID::unbind_program();
+            gl::BindProgramPipeline(self.id);
+
+            for p in programs.iter() {
+                gl::ActiveShaderProgram(self.id, p.program_id().id());
+                p.load_uniforms();
+            }
+
+            bind_blocks(&blocks);
+            if let Some(vao) = &self.attribute { vao.bind(); }
+            for (location, attr) in attributes.iter() { attr.load(location); }
+
+            mode.set_patch_vertices();
+            gl::DrawArrays(GLenum::from(mode), 0, count as GLsizei);
+
+            RawVertexArray::unbind();
+            unbind_blocks(&blocks);
+            gl::BindProgramPipeline(0);
+        }
+
+        Ok(())
+    }
+
+}
+
+impl Drop for ProgramPipeline {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgramPipelines(1, &self.id as *const GLuint); }
+    }
+}
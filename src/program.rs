@@ -3,10 +3,13 @@ use gl::types::*;
 use super::*;
 
 use std::cell::Cell;
-use std::mem::transmute;
+use std::mem::{transmute, size_of};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
-use std::ffi::CString;
+use std::ffi::{CString, CStr};
+use std::hash::Hasher;
+use std::path::Path;
+use std::fs;
 
 glenum! {
     pub enum ShaderType {
@@ -18,18 +21,75 @@ glenum! {
         [Compute COMPUTE_SHADER "Compute Shader"]
     }
 
-    pub enum DrawMode {
+    ///The primitive types that a geometry shader can take as input
+    pub enum GeometryInput {
         [Points POINTS "Points"],
         [Lines LINES "Lines"],
-        [LineStrip LINE_STRIP "Line Strip"],
-        [LineLoop LINE_LOOP "Line Loop"],
         [LinesAdjacency LINES_ADJACENCY "Lines Adjacency"],
-        [LineStripAdjacency LINE_STRIP_ADJACENCY "Line Strip Adjacency"],
         [Triangles TRIANGLES "Triangles"],
-        [TriangleStrip TRIANGLE_STRIP "Triangle Strip"],
-        [TriangleFan TRIANGLE_FAN "Triangle Fan"],
-        [TrianglesAdjacency TRIANGLES_ADJACENCY "Triangles Adjacency"],
-        [TriangleStripAdjacency TRIANGLE_STRIP_ADJACENCY "Triangle Strip Adjacency"]
+        [TrianglesAdjacency TRIANGLES_ADJACENCY "Triangles Adjacency"]
+    }
+}
+
+///
+///How vertices are assembled into primitives when drawing
+///
+///`Patches` holds the number of vertices in each patch and is required for programs with
+///tessellation shaders. `Patches(0)` takes the size of the output patches declared by the program's
+///tessellation control shader with `layout(vertices = N) out;`. Since it carries a value, this isn't
+///a plain [GLEnum] and needs to be converted with [GLenum::from] instead of `as`.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DrawMode {
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    LinesAdjacency,
+    LineStripAdjacency,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+    TrianglesAdjacency,
+    TriangleStripAdjacency,
+    Patches(GLuint)
+}
+
+impl Display for DrawMode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DrawMode::Points => write!(f, "Points"),
+            DrawMode::Lines => write!(f, "Lines"),
+            DrawMode::LineStrip => write!(f, "Line Strip"),
+            DrawMode::LineLoop => write!(f, "Line Loop"),
+            DrawMode::LinesAdjacency => write!(f, "Lines Adjacency"),
+            DrawMode::LineStripAdjacency => write!(f, "Line Strip Adjacency"),
+            DrawMode::Triangles => write!(f, "Triangles"),
+            DrawMode::TriangleStrip => write!(f, "Triangle Strip"),
+            DrawMode::TriangleFan => write!(f, "Triangle Fan"),
+            DrawMode::TrianglesAdjacency => write!(f, "Triangles Adjacency"),
+            DrawMode::TriangleStripAdjacency => write!(f, "Triangle Strip Adjacency"),
+            DrawMode::Patches(n) => write!(f, "Patches of {} Vertices", n)
+        }
+    }
+}
+
+impl From<DrawMode> for GLenum {
+    fn from(mode: DrawMode) -> GLenum {
+        match mode {
+            DrawMode::Points => gl::POINTS,
+            DrawMode::Lines => gl::LINES,
+            DrawMode::LineStrip => gl::LINE_STRIP,
+            DrawMode::LineLoop => gl::LINE_LOOP,
+            DrawMode::LinesAdjacency => gl::LINES_ADJACENCY,
+            DrawMode::LineStripAdjacency => gl::LINE_STRIP_ADJACENCY,
+            DrawMode::Triangles => gl::TRIANGLES,
+            DrawMode::TriangleStrip => gl::TRIANGLE_STRIP,
+            DrawMode::TriangleFan => gl::TRIANGLE_FAN,
+            DrawMode::TrianglesAdjacency => gl::TRIANGLES_ADJACENCY,
+            DrawMode::TriangleStripAdjacency => gl::TRIANGLE_STRIP_ADJACENCY,
+            DrawMode::Patches(_) => gl::PATCHES
+        }
     }
 }
 
@@ -43,22 +103,329 @@ impl DrawMode {
             DrawMode::Triangles => (s%3) == 0,
             DrawMode::TrianglesAdjacency => (s%6) == 0,
             DrawMode::TriangleStripAdjacency => s > 4 && (s&1) == 0,
+            DrawMode::Patches(n) => n > 0 && s % n as usize == 0,
             _ => s > 1
         }
     }
+
+    ///The primitive type captured by transform feedback when drawing with this mode
+    pub fn feedback_mode(self) -> DrawMode {
+        match self {
+            DrawMode::Points => DrawMode::Points,
+            DrawMode::Lines | DrawMode::LineStrip | DrawMode::LineLoop |
+            DrawMode::LinesAdjacency | DrawMode::LineStripAdjacency => DrawMode::Lines,
+            _ => DrawMode::Triangles
+        }
+    }
+
+    ///
+    ///The number of vertices transform feedback writes when drawing `count` vertices with this mode
+    ///and no geometry or tessellation shaders, since strips and fans are split into separate primitives
+    ///
+    pub fn feedback_vertices(self, count: usize) -> usize {
+        match self {
+            DrawMode::Points => count,
+            DrawMode::Lines => count / 2 * 2,
+            DrawMode::LineStrip => count.saturating_sub(1) * 2,
+            DrawMode::LineLoop => if count > 1 { count * 2 } else { 0 },
+            DrawMode::LinesAdjacency => count / 4 * 2,
+            DrawMode::LineStripAdjacency => count.saturating_sub(3) * 2,
+            DrawMode::Triangles => count / 3 * 3,
+            DrawMode::TriangleStrip | DrawMode::TriangleFan => count.saturating_sub(2) * 3,
+            DrawMode::TrianglesAdjacency => count / 6 * 3,
+            DrawMode::TriangleStripAdjacency => count.saturating_sub(4) / 2 * 3,
+            DrawMode::Patches(_) => 0
+        }
+    }
+
+    ///The primitive type a geometry shader gets from this mode, or `None` for patches
+    pub fn geometry_input(self) -> Option<GeometryInput> {
+        match self {
+            DrawMode::Points => Some(GeometryInput::Points),
+            DrawMode::Lines | DrawMode::LineStrip | DrawMode::LineLoop => Some(GeometryInput::Lines),
+            DrawMode::LinesAdjacency | DrawMode::LineStripAdjacency => Some(GeometryInput::LinesAdjacency),
+            DrawMode::Triangles | DrawMode::TriangleStrip | DrawMode::TriangleFan => Some(GeometryInput::Triangles),
+            DrawMode::TrianglesAdjacency | DrawMode::TriangleStripAdjacency => Some(GeometryInput::TrianglesAdjacency),
+            DrawMode::Patches(_) => None
+        }
+    }
+
+    ///
+    ///Checks that this mode can be drawn with a program that has the given stages, where patches
+    ///are needed if and only if there is a tessellation evaluation shader, and the primitives
+    ///must match the input of a geometry shader that comes directly after the vertex shader
+    ///
+    pub fn validate_stages(self, tessellation: bool, geometry: Option<GeometryInput>) -> Result<(), GLError> {
+        match self {
+            DrawMode::Patches(n) if !tessellation => return Err(GLError::InvalidOperation(
+                format!("Patches of {} vertices can only be drawn with a tessellation evaluation shader", n)
+            )),
+            DrawMode::Patches(0) => return Err(GLError::InvalidOperation(
+                "Patches need at least one vertex or a tessellation control shader that declares how many".to_owned()
+            )),
+            DrawMode::Patches(_) => (),
+            _ if tessellation => return Err(GLError::InvalidOperation(
+                format!("Programs with tessellation shaders must draw patches, not {}", self)
+            )),
+            _ => ()
+        }
+
+        //with tessellation, the geometry shader gets the output primitives of the evaluation shader instead
+        if let (false, Some(input)) = (tessellation, geometry) {
+            if self.geometry_input() != Some(input) {
+                return Err(GLError::InvalidOperation(
+                    format!("{} can't be drawn with a geometry shader taking {}", self, input)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Replaces `Patches(0)` with patches of the given size, if there is one
+    pub fn with_patch_vertices(self, declared: Option<GLuint>) -> DrawMode {
+        match (self, declared) {
+            (DrawMode::Patches(0), Some(n)) => DrawMode::Patches(n),
+            _ => self
+        }
+    }
+
+    ///
+    ///Sets the number of vertices in each patch if this mode is [Patches](DrawMode::Patches)
+    ///
+    ///# Unsafety
+    ///
+    ///This modifies global GL state, so it should be called right before drawing
+    ///
+    pub unsafe fn set_patch_vertices(self) {
+        if let DrawMode::Patches(n) = self { gl::PatchParameteri(gl::PATCH_VERTICES, n as GLint); }
+    }
+}
+
+mod index_type {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+///The integer types that can be used as the indices of an element array buffer
+pub unsafe trait IndexType: Copy + index_type::Sealed {
+    fn int_type() -> IntType;
+    fn to_u32(self) -> u32;
+}
+
+unsafe impl IndexType for u8 {
+    #[inline] fn int_type() -> IntType { IntType::UByte }
+    #[inline] fn to_u32(self) -> u32 { self as u32 }
+}
+unsafe impl IndexType for u16 {
+    #[inline] fn int_type() -> IntType { IntType::UShort }
+    #[inline] fn to_u32(self) -> u32 { self as u32 }
+}
+unsafe impl IndexType for u32 {
+    #[inline] fn int_type() -> IntType { IntType::UInt }
+    #[inline] fn to_u32(self) -> u32 { self }
+}
+
+///
+///Checks that the indices make up whole primitives of the given mode and, if the number of
+///vertices is given, that every index plus `base_vertex` is within it
+///
+///With primitive restart, the primitives can have any number of vertices and the restart index
+///is skipped. Note that checking the indices reads them back from the GPU, which waits on
+///anything still writing to the buffer.
+///
+pub fn check_elements<I:IndexType, A:BufferAccess>(
+    mode: DrawMode, restart_index: Option<GLuint>, indices: &Buffer<[I], A>, base_vertex: GLint,
+    vertices: Option<usize>
+) -> Result<(), GLError> {
+    if restart_index.is_none() && !mode.valid_array_size(indices.len()) {
+        return Err(GLError::InvalidOperation(format!("{} indices can't be drawn as {}", indices.len(), mode)));
+    }
+
+    let vertices = match vertices {
+        Some(vertices) if indices.len() > 0 => vertices as i64,
+        _ => return Ok(())
+    };
+
+    //GetBufferSubData works no matter how the buffer can be mapped
+    let mut data = vec![0u8; indices.len() * size_of::<I>()];
+    unsafe {
+        BufferTarget::CopyReadBuffer.bind(indices);
+        gl::GetBufferSubData(
+            gl::COPY_READ_BUFFER, indices.data_offset() as GLintptr, data.len() as GLsizeiptr,
+            data.as_mut_ptr() as *mut GLvoid
+        );
+        BufferTarget::CopyReadBuffer.unbind();
+    }
+
+    for (i, index) in data.chunks(size_of::<I>()).enumerate() {
+        let index = unsafe { ::std::ptr::read_unaligned(index.as_ptr() as *const I) }.to_u32();
+        if Some(index) == restart_index { continue; }
+
+        let vertex = index as i64 + base_vertex as i64;
+        if vertex < 0 || vertex >= vertices {
+            return Err(GLError::InvalidOperation(format!(
+                "Index {} at position {} reads vertex {} out of {}", index, i, vertex, vertices
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+///
+///Binds the index buffer and draws using all of its indices, offset by `base_vertex`
+///
+///# Unsafety
+///
+///The caller must make sure a program is in use, all of its inputs are bound, and that every index
+///(plus the base vertex) is within the bounds of the bound attribute arrays, such as with [check_elements]
+///
+pub unsafe fn draw_elements<I:IndexType, A:BufferAccess>(mode: DrawMode, indices: &Buffer<[I], A>, base_vertex: GLint) {
+    BufferTarget::ElementArrayBuffer.bind(indices);
+    let offset = indices.data_offset() as *const GLvoid;
+    if base_vertex==0 {
+        gl::DrawElements(GLenum::from(mode), indices.len() as GLsizei, I::int_type() as GLenum, offset);
+    } else {
+        gl::DrawElementsBaseVertex(
+            GLenum::from(mode), indices.len() as GLsizei, I::int_type() as GLenum, offset, base_vertex
+        );
+    }
+    BufferTarget::ElementArrayBuffer.unbind();
+}
+
+///
+///The same as [draw_elements], but drawing `instances` instances starting from `base_instance`
+///
+///# Unsafety
+///
+///The same as for [draw_elements], and the instanced attribute arrays must also be long enough for
+///every instance drawn
+///
+pub unsafe fn draw_elements_instanced<I:IndexType, A:BufferAccess>(
+    mode: DrawMode, indices: &Buffer<[I], A>, instances: usize, base_vertex: GLint, base_instance: GLuint
+) {
+    BufferTarget::ElementArrayBuffer.bind(indices);
+    gl::DrawElementsInstancedBaseVertexBaseInstance(
+        GLenum::from(mode), indices.len() as GLsizei, I::int_type() as GLenum,
+        indices.data_offset() as *const GLvoid, instances as GLsizei, base_vertex, base_instance
+    );
+    BufferTarget::ElementArrayBuffer.unbind();
+}
+
+//
+//The parameter structs read by the glDraw*Indirect and glDispatchComputeIndirect functions.
+//Since these are laid out exactly as GL expects, they can be written directly by a compute shader
+//as a std430 buffer and then used to draw without ever reading back to the CPU
+//
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct DrawArraysIndirectCommand {
+    pub count: GLuint,
+    pub instance_count: GLuint,
+    pub first: GLuint,
+    pub base_instance: GLuint
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct DrawElementsIndirectCommand {
+    pub count: GLuint,
+    pub instance_count: GLuint,
+    pub first_index: GLuint,
+    pub base_vertex: GLint,
+    pub base_instance: GLuint
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct DispatchIndirectCommand {
+    pub num_groups_x: GLuint,
+    pub num_groups_y: GLuint,
+    pub num_groups_z: GLuint
+}
+
+unsafe impl Layout<std430> for DrawArraysIndirectCommand {}
+unsafe impl Layout<std430> for DrawElementsIndirectCommand {}
+unsafe impl Layout<std430> for DispatchIndirectCommand {}
+
+impl DrawArraysIndirectCommand {
+
+    ///
+    ///Runs glDrawArraysIndirect once for every command in the buffer (requires GL 4.0)
+    ///
+    ///# Unsafety
+    ///
+    ///The caller must make sure a program is in use and all of its inputs are bound
+    ///
+    pub unsafe fn draw<A:BufferAccess>(mode: DrawMode, commands: &Buffer<[Self], A>) -> Result<(), GLError> {
+        check_loaded!(DrawArraysIndirect; ())?;
+        BufferTarget::DrawIndirectBuffer.bind(commands);
+        for i in 0..commands.len() {
+            let offset = commands.data_offset() + i * size_of::<Self>();
+            gl::DrawArraysIndirect(GLenum::from(mode), offset as *const GLvoid);
+        }
+        BufferTarget::DrawIndirectBuffer.unbind();
+        Ok(())
+    }
+
+    ///
+    ///Runs every command in the buffer with a single call to glMultiDrawArraysIndirect (requires GL 4.3)
+    ///
+    ///# Unsafety
+    ///
+    ///The caller must make sure a program is in use and all of its inputs are bound
+    ///
+    pub unsafe fn multi_draw<A:BufferAccess>(mode: DrawMode, commands: &Buffer<[Self], A>) -> Result<(), GLError> {
+        check_loaded!(MultiDrawArraysIndirect; ())?;
+        BufferTarget::DrawIndirectBuffer.bind(commands);
+        gl::MultiDrawArraysIndirect(
+            GLenum::from(mode), commands.data_offset() as *const GLvoid, commands.len() as GLsizei, 0
+        );
+        BufferTarget::DrawIndirectBuffer.unbind();
+        Ok(())
+    }
+
+}
+
+impl DispatchIndirectCommand {
+
+    ///
+    ///Dispatches the currently used compute program with the work group counts stored in the buffer
+    ///
+    ///# Unsafety
+    ///
+    ///The caller must make sure a compute program is in use and all of its inputs are bound
+    ///
+    pub unsafe fn dispatch<A:BufferAccess>(command: &Buffer<Self, A>) {
+        BufferTarget::DispatchIndirectBuffer.bind(command);
+        gl::DispatchComputeIndirect(command.data_offset() as GLintptr);
+        BufferTarget::DispatchIndirectBuffer.unbind();
+    }
+
 }
 
 pub struct Shader {
     id: GLuint,
-    ty: ShaderType
+    ty: ShaderType,
+    spirv: bool
 }
 
 impl Shader {
 
-    pub fn create(_gl: &GLProvider, src: &str, ty: ShaderType) -> Result<Self, GLError> {
+    pub fn create(context: &GLProvider, src: &str, ty: ShaderType) -> Result<Self, GLError> {
         unsafe {
             //create the shader
-            let s = Shader {id: gl::CreateShader(ty.into()), ty: ty};
+            let s = Shader::from_raw(gl::CreateShader(ty.into()), ty, false);
+
+            //resolve any includes
+            let (src, includes) = match context.shader_fs().preprocess(src) {
+                Ok(res) => res,
+                Err(log) => return Err(GLError::ShaderCompilation(s.id, ty, log))
+            };
 
             let len = src.len() as GLint;
             let src_array = &src.as_bytes()[0];
@@ -69,7 +436,11 @@ impl Shader {
 
             //error check
             if s.get_shader_int(gl::COMPILE_STATUS) == gl::FALSE as GLint {
-                Err(GLError::ShaderCompilation(s.id, ty, s.shader_info_log()))
+                let mut log = s.shader_info_log();
+                for (i, name) in includes.iter().enumerate() {
+                    log.push_str(&format!("\n{}: \"{}\"", i+1, name));
+                }
+                Err(GLError::ShaderCompilation(s.id, ty, log))
             } else {
                 Ok(s)
             }
@@ -77,15 +448,23 @@ impl Shader {
 
     }
 
+    #[inline] pub(crate) fn from_raw(id: GLuint, ty: ShaderType, spirv: bool) -> Self {
+        Shader { id: id, ty: ty, spirv: spirv }
+    }
+
+    #[inline] pub fn id(&self) -> GLuint { self.id }
     #[inline] pub fn shader_type(&self) -> ShaderType { self.ty }
 
-    unsafe fn get_shader_int(&self, p: GLenum) -> GLint {
+    ///Whether this shader was created from a SPIR-V module instead of GLSL source
+    #[inline] pub fn is_spirv(&self) -> bool { self.spirv }
+
+    pub(crate) unsafe fn get_shader_int(&self, p: GLenum) -> GLint {
         let mut val:GLint = 0;
         gl::GetShaderiv(self.id, p, &mut val as *mut GLint);
         val
     }
 
-    fn shader_info_log(&self) -> String {
+    pub(crate) fn shader_info_log(&self) -> String {
         unsafe {
             let len = self.get_shader_int(gl::INFO_LOG_LENGTH);
             let mut actual: GLint = 0;
@@ -108,7 +487,8 @@ impl Drop for Shader {
 }
 
 pub struct ProgramID {
-    id: GLuint
+    id: GLuint,
+    stages: Vec<ShaderType>
 }
 
 impl ProgramID {
@@ -122,47 +502,349 @@ impl ProgramID {
     }
 
     pub fn from_shaders(_gl: &GLProvider, shaders: Vec<Shader>) -> Result<Self, GLError> {
+        unsafe { Self::link(shaders, |_| ()) }
+    }
+
+    ///
+    ///Creates a program where the given outputs of the last vertex processing stage are captured
+    ///into separate buffers using transform feedback (in the order given)
+    ///
+    pub fn from_source_with_captures(_gl: &GLProvider, shaders: Vec<(&str, ShaderType)>, captures: &[&str]) -> Result<Self, GLError> {
+        let mut list = Vec::with_capacity(shaders.len());
+        for (src, ty) in shaders.iter() {
+            list.push(Shader::create(_gl, src, *ty)?);
+        }
+        Self::from_shaders_with_captures(_gl, list, captures)
+    }
+
+    pub fn from_shaders_with_captures(_gl: &GLProvider, shaders: Vec<Shader>, captures: &[&str]) -> Result<Self, GLError> {
+        Self::from_shaders_with_locations(_gl, shaders, captures, &[])
+    }
+
+    ///
+    ///The same as [from_source_with_captures](Self::from_source_with_captures), but also binding
+    ///the given vertex attributes to fixed locations before linking
+    ///
+    ///Any attributes with a `layout(location = N)` qualifier keep their location from the source.
+    ///This way, programs that use the same locations can all share the same vertex array layouts.
+    ///
+    pub fn from_source_with_locations(
+        _gl: &GLProvider, shaders: Vec<(&str, ShaderType)>, captures: &[&str], locations: &[(&str, GLuint)]
+    ) -> Result<Self, GLError> {
+        let mut list = Vec::with_capacity(shaders.len());
+        for (src, ty) in shaders.iter() {
+            list.push(Shader::create(_gl, src, *ty)?);
+        }
+        Self::from_shaders_with_locations(_gl, list, captures, locations)
+    }
+
+    pub fn from_shaders_with_locations(
+        _gl: &GLProvider, shaders: Vec<Shader>, captures: &[&str], locations: &[(&str, GLuint)]
+    ) -> Result<Self, GLError> {
+        unsafe { Self::link(shaders, |program| program.bind_locations(captures, locations)) }
+    }
+
+    ///
+    ///Creates a program with `GL_PROGRAM_SEPARABLE` set, so that its stages can be mixed with the
+    ///stages of other separable programs in a [ProgramPipeline]
+    ///
+    ///Requires GL 4.1 or `ARB_separate_shader_objects`
+    ///
+    pub fn from_source_separable(
+        _gl: &GLProvider, shaders: Vec<(&str, ShaderType)>, captures: &[&str], locations: &[(&str, GLuint)]
+    ) -> Result<Self, GLError> {
+        let mut list = Vec::with_capacity(shaders.len());
+        for (src, ty) in shaders.iter() {
+            list.push(Shader::create(_gl, src, *ty)?);
+        }
+        Self::from_shaders_separable(_gl, list, captures, locations)
+    }
 
+    pub fn from_shaders_separable(
+        _gl: &GLProvider, shaders: Vec<Shader>, captures: &[&str], locations: &[(&str, GLuint)]
+    ) -> Result<Self, GLError> {
         unsafe {
-            //create the program
-            let id = gl::CreateProgram();
-            let program = ProgramID{id: id};
+            Self::link(shaders, |program| {
+                gl::ProgramParameteri(program.id, gl::PROGRAM_SEPARABLE, gl::TRUE as GLint);
+                program.bind_locations(captures, locations);
+            })
+        }
+    }
+
+    unsafe fn bind_locations(&self, captures: &[&str], locations: &[(&str, GLuint)]) {
+        for (name, loc) in locations.iter() {
+            let name = CString::new(*name).unwrap();
+            gl::BindAttribLocation(self.id, *loc, name.as_ptr());
+        }
+
+        if captures.len() > 0 {
+            //the varyings have to be set before linking, so we keep the CStrings alive until then
+            let names: Vec<CString> = captures.iter().map(|name| CString::new(*name).unwrap()).collect();
+            let ptrs: Vec<*const GLchar> = names.iter().map(|name| name.as_ptr()).collect();
+            gl::TransformFeedbackVaryings(self.id, ptrs.len() as GLsizei, &ptrs[0], gl::SEPARATE_ATTRIBS);
+        }
+    }
 
-            //attach the shaders
-            for shader in shaders.iter() {
-                gl::AttachShader(id, shader.id);
+    ///
+    ///The same as [from_source_with_locations](Self::from_source_with_locations), but caching the
+    ///linked program binary in the given directory in order to skip compilation on later runs
+    ///
+    ///The binaries are keyed by the source of each stage (with its includes resolved), the captures
+    ///and locations, and the
+    ///`GL_RENDERER` and `GL_VERSION` strings, so changing any of them (or the driver) simply
+    ///causes a new binary to be made. If a cached binary is rejected by the driver, or if program
+    ///binaries aren't supported at all, the program is compiled from source as usual.
+    ///
+    ///Do note that failing to write to the cache is _not_ an error, since the program itself
+    ///was still created successfully.
+    ///
+    pub fn from_source_cached<P:AsRef<Path>>(
+        _gl: &GLProvider, shaders: Vec<(&str, ShaderType)>, captures: &[&str], locations: &[(&str, GLuint)],
+        cache_dir: P
+    ) -> Result<Self, GLError> {
+        Self::cached(_gl, shaders, captures, locations, cache_dir.as_ref(), false)
+    }
+
+    ///The same as [from_source_cached](Self::from_source_cached), but for a separable program
+    pub fn from_source_separable_cached<P:AsRef<Path>>(
+        _gl: &GLProvider, shaders: Vec<(&str, ShaderType)>, captures: &[&str], locations: &[(&str, GLuint)],
+        cache_dir: P
+    ) -> Result<Self, GLError> {
+        Self::cached(_gl, shaders, captures, locations, cache_dir.as_ref(), true)
+    }
+
+    fn cached(
+        _gl: &GLProvider, shaders: Vec<(&str, ShaderType)>, captures: &[&str], locations: &[(&str, GLuint)],
+        cache_dir: &Path, separable: bool
+    ) -> Result<Self, GLError> {
+
+        let uncached = |shaders| if separable {
+            Self::from_source_separable(_gl, shaders, captures, locations)
+        } else {
+            Self::from_source_with_locations(_gl, shaders, captures, locations)
+        };
+
+        if !gl::ProgramBinary::is_loaded() || !gl::GetProgramBinary::is_loaded() {
+            return uncached(shaders);
+        }
+
+        //the key uses the sources after preprocessing so that changing an included file makes a new binary
+        let mut resolved = Vec::with_capacity(shaders.len());
+        for (src, ty) in shaders.iter() {
+            match _gl.shader_fs().preprocess(src) {
+                Ok((src, _)) => resolved.push((src, *ty)),
+                Err(_) => return uncached(shaders) //compiling reports the error properly
             }
+        }
 
-            //we want to error check the link and validation, but since we also need to detatch the
-            //shaders, we need to store the result temporarily
-            let res = {
-                //link and error check
-                gl::LinkProgram(id);
-                if program.get_program_int(gl::LINK_STATUS) == gl::FALSE as GLint {
-                    Err(GLError::ProgramLinking(id, program.program_info_log()))
-                } else {
-                    //validate and error error check
-                    gl::ValidateProgram(id);
-                    if program.get_program_int(gl::VALIDATE_STATUS) == gl::FALSE as GLint {
-                        Err(GLError::ProgramValidation(id, program.program_info_log()))
-                    } else {
-                        Ok(program)
+        unsafe {
+            let key = Self::cache_key(&resolved, captures, locations, separable);
+            let path = cache_dir.join(format!("{:016x}.bin", key));
+
+            //try loading the binary and fall back to compiling if anything goes wrong
+            if let Ok(data) = fs::read(&path) {
+                if data.len() > size_of::<GLenum>() {
+                    let (format, binary) = data.split_at(size_of::<GLenum>());
+                    let format = GLenum::from_le_bytes([format[0], format[1], format[2], format[3]]);
+
+                    let program = ProgramID{id: gl::CreateProgram(), stages: shaders.iter().map(|s| s.1).collect()};
+                    if separable { gl::ProgramParameteri(program.id, gl::PROGRAM_SEPARABLE, gl::TRUE as GLint); }
+                    gl::ProgramBinary(program.id, format, binary.as_ptr() as *const GLvoid, binary.len() as GLsizei);
+                    if program.get_program_int(gl::LINK_STATUS) != gl::FALSE as GLint {
+                        program.validate()?;
+                        return Ok(program);
                     }
                 }
-            };
+            }
 
-            //detach the shaders
-            for shader in shaders.iter() {
-                gl::DetachShader(id, shader.id);
+            let mut list = Vec::with_capacity(shaders.len());
+            for (src, ty) in shaders.iter() {
+                list.push(Shader::create(_gl, src, *ty)?);
             }
 
-            res
+            let program = Self::link(list, |program| {
+                gl::ProgramParameteri(program.id, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as GLint);
+                if separable { gl::ProgramParameteri(program.id, gl::PROGRAM_SEPARABLE, gl::TRUE as GLint); }
+                program.bind_locations(captures, locations);
+            })?;
+
+            let len = program.get_program_int(gl::PROGRAM_BINARY_LENGTH);
+            if len > 0 {
+                let mut format: GLenum = 0;
+                let mut binary = vec![0u8; len as usize];
+                gl::GetProgramBinary(
+                    program.id, len, ::std::ptr::null_mut(), &mut format as *mut GLenum,
+                    binary.as_mut_ptr() as *mut GLvoid
+                );
+
+                let mut data = format.to_le_bytes().to_vec();
+                data.extend_from_slice(&binary);
+                let _ = fs::create_dir_all(cache_dir).and_then(|_| fs::write(&path, data));
+            }
+
+            Ok(program)
+        }
+
+    }
+
+    unsafe fn cache_key(
+        shaders: &[(String, ShaderType)], captures: &[&str], locations: &[(&str, GLuint)], separable: bool
+    ) -> u64 {
+        let gl_string = |name: GLenum| {
+            let s = gl::GetString(name);
+            if s.is_null() { Vec::new() } else { CStr::from_ptr(s as *const GLchar).to_bytes().to_vec() }
+        };
+
+        //everything is written as plain bytes since the encoding used by the Hash impls could change,
+        //and strings and lists are prefixed with their length so that they can't run into each other
+        let mut hasher = FnvHasher::default();
+        let mut write_bytes = |bytes: &[u8]| {
+            hasher.write(&(bytes.len() as u64).to_le_bytes());
+            hasher.write(bytes);
+        };
+
+        write_bytes(&gl_string(gl::RENDERER));
+        write_bytes(&gl_string(gl::VERSION));
+        write_bytes(&(shaders.len() as u64).to_le_bytes());
+        for (src, ty) in shaders.iter() {
+            write_bytes(&GLenum::from(*ty).to_le_bytes());
+            write_bytes(src.as_bytes());
+        }
+        write_bytes(&(captures.len() as u64).to_le_bytes());
+        for name in captures.iter() { write_bytes(name.as_bytes()); }
+        for (name, loc) in locations.iter() {
+            write_bytes(name.as_bytes());
+            write_bytes(&loc.to_le_bytes());
+        }
+        write_bytes(&[separable as u8]);
+        hasher.finish()
+    }
+
+    //attaches and links the shaders while giving a chance to change program settings before the link
+    unsafe fn link<F:FnOnce(&ProgramID)>(shaders: Vec<Shader>, pre_link: F) -> Result<Self, GLError> {
+
+        //the driver would only give a link error for this
+        if shaders.iter().any(|s| s.spirv) && shaders.iter().any(|s| !s.spirv) {
+            return Err(GLError::InvalidOperation("SPIR-V and GLSL shaders can't be linked into the same program".to_owned()));
+        }
+
+        //create the program
+        let id = gl::CreateProgram();
+        let program = ProgramID{id: id, stages: shaders.iter().map(|s| s.ty).collect()};
+
+        //attach the shaders
+        for shader in shaders.iter() {
+            gl::AttachShader(id, shader.id);
+        }
+
+        pre_link(&program);
+
+        //we want to error check the link and validation, but since we also need to detatch the
+        //shaders, we need to store the result temporarily
+        let res = {
+            //link and error check
+            gl::LinkProgram(id);
+            if program.get_program_int(gl::LINK_STATUS) == gl::FALSE as GLint {
+                Err(GLError::ProgramLinking(id, program.program_info_log()))
+            } else {
+                program.validate().map(|_| program)
+            }
+        };
+
+        //detach the shaders
+        for shader in shaders.iter() {
+            gl::DetachShader(id, shader.id);
+        }
+
+        res
+    }
+
+    //validates the linked program and error checks it
+    unsafe fn validate(&self) -> Result<(), GLError> {
+        gl::ValidateProgram(self.id);
+        if self.get_program_int(gl::VALIDATE_STATUS) == gl::FALSE as GLint {
+            Err(GLError::ProgramValidation(self.id, self.program_info_log()))
+        } else {
+            Ok(())
         }
     }
 
+    #[inline] pub fn id(&self) -> GLuint { self.id }
+
     #[inline] pub unsafe fn use_program(&self) { gl::UseProgram(self.id); }
     #[inline] pub unsafe fn unbind_program() { gl::UseProgram(0); }
 
+    ///The shader stages this program was linked with
+    #[inline] pub fn stages(&self) -> &[ShaderType] { &self.stages }
+
+    ///The input primitive of this program's geometry shader, if it has one
+    pub fn geometry_input(&self) -> Option<GeometryInput> {
+        if !self.stages.contains(&ShaderType::Geometry) { return None; }
+        let ty = unsafe { self.get_program_int(gl::GEOMETRY_INPUT_TYPE) };
+        GeometryInput::try_from(ty as GLenum).ok()
+    }
+
+    ///The output primitive of this program's geometry shader, if it has one
+    pub fn geometry_output(&self) -> Option<DrawMode> {
+        if !self.stages.contains(&ShaderType::Geometry) { return None; }
+        match unsafe { self.get_program_int(gl::GEOMETRY_OUTPUT_TYPE) } as GLenum {
+            gl::POINTS => Some(DrawMode::Points),
+            gl::LINE_STRIP => Some(DrawMode::LineStrip),
+            gl::TRIANGLE_STRIP => Some(DrawMode::TriangleStrip),
+            _ => None
+        }
+    }
+
+    ///The primitives generated by this program's tessellation evaluation shader, if it has one
+    pub fn tessellation_output(&self) -> Option<DrawMode> {
+        if !self.stages.contains(&ShaderType::TessEval) { return None; }
+        unsafe {
+            if self.get_program_int(gl::TESS_GEN_POINT_MODE) != gl::FALSE as GLint {
+                return Some(DrawMode::Points);
+            }
+            match self.get_program_int(gl::TESS_GEN_MODE) as GLenum {
+                gl::ISOLINES => Some(DrawMode::Lines),
+                gl::TRIANGLES | gl::QUADS => Some(DrawMode::Triangles),
+                _ => None
+            }
+        }
+    }
+
+    ///
+    ///The primitive type transform feedback captures when drawing with the given mode, which
+    ///comes from the geometry or tessellation shaders if this program has them
+    ///
+    pub fn feedback_mode(&self, mode: DrawMode) -> DrawMode {
+        self.geometry_output().or_else(|| self.tessellation_output()).unwrap_or(mode).feedback_mode()
+    }
+
+    ///The size of the output patches of this program's tessellation control shader, if it has one
+    pub fn patch_vertices(&self) -> Option<GLuint> {
+        if !self.stages.contains(&ShaderType::TessControl) { return None; }
+        Some(unsafe { self.get_program_int(gl::TESS_CONTROL_OUTPUT_VERTICES) } as GLuint)
+    }
+
+    ///Whether this program was made with one of the `separable` constructors
+    #[inline] pub fn is_separable(&self) -> bool { unsafe { self.get_program_int(gl::PROGRAM_SEPARABLE) != 0 } }
+
+    ///
+    ///The texture unit that the samplers of this program start at when it's separable
+    ///
+    ///Programs used together in a [ProgramPipeline] all share the same texture units, so each gets
+    ///its own range of 16 units (the minimum each stage is guaranteed) starting from that of the
+    ///first stage it has.
+    ///
+    pub fn first_texture_unit(&self) -> GLuint {
+        let stage = |s: &ShaderType| match s {
+            ShaderType::Vertex | ShaderType::Compute => 0,
+            ShaderType::TessControl => 1,
+            ShaderType::TessEval => 2,
+            ShaderType::Geometry => 3,
+            ShaderType::Fragment => 4,
+        };
+        self.stages.iter().map(stage).min().unwrap_or(0) * 16
+    }
+
     unsafe fn get_program_int(&self, p: GLenum) -> GLint {
         let mut val:GLint = 0;
         gl::GetProgramiv(self.id, p, &mut val as *mut GLint);
@@ -185,6 +867,23 @@ impl ProgramID {
 
 }
 
+//64-bit FNV-1a, used for the binary cache keys since it's the same from run to run
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self { FnvHasher(0xcbf29ce484222325) }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 { self.0 }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ *b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
 impl Drop for ProgramID {
     fn drop(&mut self) {
         unsafe { gl::DeleteProgram(self.id); }
@@ -263,6 +962,24 @@ impl UniformLocation {
         }
     }
 
+    ///
+    ///Points the samplers in this uniform to the texture units counting up from `first`, returning
+    ///the first unit after them
+    ///
+    ///The units are reserved even if the uniform is inactive so that the result doesn't depend on
+    ///what the driver optimizes out. The program must be bound.
+    ///
+    pub unsafe fn assign_texture_units<T:GLSLType>(&self, first: GLuint) -> GLuint {
+        if self.id >= 0 { T::assign_texture_units(self.id, first); }
+        first + T::texture_units()
+    }
+
+    ///Binds the textures of the samplers in the given value to the units given by [assign_texture_units](Self::assign_texture_units)
+    pub unsafe fn bind_textures<T:GLSLType>(&self, value: &Uniform<T>, first: GLuint) -> GLuint {
+        if self.id >= 0 { value.bind_textures(first); }
+        first + T::texture_units()
+    }
+
     #[inline] fn is_loaded<T:GLSLType>(&self, value: &Uniform<T>) -> bool {
         let (id, pid) = value.location.get();
         value.loaded.get() && id == self.id && pid == self.pid
@@ -276,6 +993,35 @@ pub enum Attribute<'a, A:GLSLType> {
     Array(AttribArray<'a, A>)
 }
 
+///
+///The layout of an attribute within the vertices of whatever buffer is bound to the given binding
+///index, for use with separate vertex formats and bindings (GL 4.3)
+///
+#[derive(Clone, Copy, Debug)]
+pub struct AttribBinding<A:GLSLType> {
+    pub format: A::AttributeFormat,
+    pub relative_offset: usize,
+    pub binding: GLuint
+}
+
+impl<A:GLSLType> AttribBinding<A> {
+    #[inline]
+    pub fn new<U:AttributeData<A>>(binding: GLuint, relative_offset: usize) -> Self {
+        AttribBinding { format: U::format(), relative_offset: relative_offset, binding: binding }
+    }
+}
+
+impl<'a, A:GLSLType> Attribute<'a, A> {
+    ///The number of vertices that can be read from this attribute, or `None` if it isn't per-vertex
+    #[inline]
+    pub fn vertex_count(&self) -> Option<usize> {
+        match self {
+            Attribute::Array(arr) if arr.divisor()==0 => Some(arr.len()),
+            _ => None
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AttributeLocation {
     id: GLint
@@ -283,12 +1029,27 @@ pub struct AttributeLocation {
 
 impl AttributeLocation {
 
+    #[inline] pub(crate) fn from_raw(id: GLint) -> Self { AttributeLocation {id: id} }
+
     pub fn get(p: &ProgramID, name: &str) -> Result<Self, Self> {
         let id = unsafe { gl::GetAttribLocation(p.id, CString::new(name).unwrap().into_raw()) };
         let loc = AttributeLocation {id: id};
         if id<0 {Err(loc)} else {Ok(loc)}
     }
 
+    ///The location of this attribute, or -1 if it is unused by the program
+    #[inline] pub fn id(&self) -> GLint { self.id }
+
+    ///Sets the format of this attribute and which vertex buffer binding it reads from
+    pub unsafe fn load_format<A:GLSLType>(&self, b: &AttribBinding<A>) {
+        if self.id < 0 {return};
+        A::attribute_format(self.id as GLuint, b.format, b.relative_offset);
+        for i in 0..b.format.attrib_count() {
+            gl::EnableVertexAttribArray(self.id as GLuint + i as GLuint);
+            gl::VertexAttribBinding(self.id as GLuint + i as GLuint, b.binding);
+        }
+    }
+
     #[inline]
     pub unsafe fn load<'a, A:GLSLType>(&self, a: &Attribute<'a, A>) {
         if self.id < 0 {return};
@@ -309,6 +1070,13 @@ impl AttributeLocation {
                 arr.bind();
                 A::bind_attribute(self.id as GLuint, arr.format(), arr.stride(), arr.offset());
                 AttribArray::<'a, A>::unbind();
+
+                //always set, since the divisor sticks around in the VAO after the draw
+                if gl::VertexAttribDivisor::is_loaded() {
+                    for i in 0..arr.format().attrib_count() {
+                        gl::VertexAttribDivisor(self.id as GLuint + i as GLuint, arr.divisor());
+                    }
+                }
             }
         }
     }
@@ -417,4 +1185,143 @@ pub struct SubroutineLocation {
 
 impl SubroutineLocation {
 
+    pub fn get(p: &ProgramID, stage: ShaderType, name: &str) -> Result<Self, Self> {
+        let id = unsafe {
+            gl::GetSubroutineUniformLocation(p.id, stage as GLenum, CString::new(name).unwrap().as_ptr())
+        };
+        let loc = SubroutineLocation { id: id, stage: stage, pid: p.id };
+        if id<0 {Err(loc)} else {Ok(loc)}
+    }
+
+    ///The location of this subroutine uniform, or -1 if it is unused by the program
+    #[inline] pub fn id(&self) -> GLint { self.id }
+    #[inline] pub fn stage(&self) -> ShaderType { self.stage }
+
+    ///Gets the index of the given function in this uniform's stage, if it is active
+    pub fn index_of<S:GLSLSubroutine>(&self, function: S) -> Option<GLuint> {
+        let index = unsafe { gl::GetSubroutineIndex(self.pid, self.stage as GLenum, function.function_name().as_ptr()) };
+        if index==gl::INVALID_INDEX {None} else {Some(index)}
+    }
+
+    #[inline]
+    pub fn get_subroutine<S:GLSLSubroutine>(&self) -> Subroutine<S> {
+        Subroutine { value: None, index: None, location: *self }
+    }
+
+}
+
+///
+///The value of a subroutine uniform, which picks which function of type `S` gets called
+///
+///Until one is set, the uniform uses the first function the driver lists as compatible with it.
+///
+pub struct Subroutine<S:GLSLSubroutine> {
+    value: Option<S>,
+    index: Option<GLuint>,
+    location: SubroutineLocation
+}
+
+impl<S:GLSLSubroutine> Subroutine<S> {
+
+    #[inline] pub fn get(&self) -> Option<S> { self.value }
+
+    pub fn set(&mut self, function: S) {
+        if self.value == Some(function) { return; }
+        self.index = self.location.index_of(function);
+        debug_assert!(
+            self.location.id < 0 || self.index.is_some(),
+            "{:?} is not an active function of the {}", function.function_name(), self.location.stage
+        );
+        self.value = Some(function);
+    }
+
+    #[inline] pub fn location(&self) -> SubroutineLocation { self.location }
+
+    ///The index of the chosen function in its stage, or `None` if the default is used
+    #[inline] pub fn index(&self) -> Option<GLuint> { self.index }
+
+}
+
+///
+///The subroutine uniforms of every stage of a program
+///
+///Unlike normal uniforms, these are all set at once for each stage with `glUniformSubroutinesuiv`,
+///and the values are lost whenever the program is bound again, so they need to be loaded before
+///every draw or dispatch.
+///
+pub struct SubroutineUniforms {
+    //the default function for each location of each stage
+    stages: Vec<(ShaderType, Vec<GLuint>)>
+}
+
+impl SubroutineUniforms {
+
+    pub fn get(p: &ProgramID) -> Self {
+        //subroutines require GL 4.0
+        if !gl::GetProgramStageiv::is_loaded() { return SubroutineUniforms { stages: Vec::new() }; }
+
+        let stages = p.stages().iter().map(|&stage| unsafe {
+            let mut count = 0;
+            gl::GetProgramStageiv(p.id, stage as GLenum, gl::ACTIVE_SUBROUTINE_UNIFORMS, &mut count as *mut GLint);
+
+            let mut locations = 0;
+            gl::GetProgramStageiv(
+                p.id, stage as GLenum, gl::ACTIVE_SUBROUTINE_UNIFORM_LOCATIONS, &mut locations as *mut GLint
+            );
+
+            let mut defaults = vec![0; locations.max(0) as usize];
+            for i in 0..count.max(0) as GLuint {
+                let mut len = 0;
+                gl::GetActiveSubroutineUniformiv(p.id, stage as GLenum, i, gl::UNIFORM_NAME_LENGTH, &mut len as *mut GLint);
+                let mut name = vec![0u8; len.max(1) as usize];
+                gl::GetActiveSubroutineUniformName(
+                    p.id, stage as GLenum, i, len, ::std::ptr::null_mut(), name.as_mut_ptr() as *mut GLchar
+                );
+                let location = gl::GetSubroutineUniformLocation(p.id, stage as GLenum, name.as_ptr() as *const GLchar);
+
+                let mut size = 0;
+                gl::GetActiveSubroutineUniformiv(p.id, stage as GLenum, i, gl::UNIFORM_SIZE, &mut size as *mut GLint);
+
+                let mut num = 0;
+                gl::GetActiveSubroutineUniformiv(p.id, stage as GLenum, i, gl::NUM_COMPATIBLE_SUBROUTINES, &mut num as *mut GLint);
+                let mut compatible = vec![0 as GLint; num.max(0) as usize];
+                if compatible.len() > 0 {
+                    gl::GetActiveSubroutineUniformiv(
+                        p.id, stage as GLenum, i, gl::COMPATIBLE_SUBROUTINES, compatible.as_mut_ptr()
+                    );
+                }
+
+                //arrays take up one location per element
+                if let Some(&first) = compatible.iter().min() {
+                    for j in 0..size.max(1) {
+                        if let Some(d) = defaults.get_mut((location + j) as usize) { *d = first as GLuint; }
+                    }
+                }
+            }
+
+            (stage, defaults)
+        }).collect();
+
+        SubroutineUniforms { stages: stages }
+    }
+
+    ///
+    ///Sets every subroutine uniform of the currently bound program, using the given index for
+    ///each location it is `Some` for and the default everywhere else
+    ///
+    pub unsafe fn load(&self, values: &[(SubroutineLocation, Option<GLuint>)]) {
+        for (stage, defaults) in self.stages.iter() {
+            if defaults.len()==0 { continue; }
+
+            let mut indices = defaults.clone();
+            for (loc, index) in values.iter() {
+                if let (true, Some(index)) = (loc.stage==*stage && loc.id >= 0, index) {
+                    indices[loc.id as usize] = *index;
+                }
+            }
+
+            gl::UniformSubroutinesuiv(*stage as GLenum, indices.len() as GLsizei, indices.as_ptr());
+        }
+    }
+
 }
@@ -0,0 +1,398 @@
+use super::*;
+
+use std::convert::TryFrom;
+use std::ffi::CString;
+
+glenum! {
+    ///The GLSL type of a variable in a program interface
+    pub enum DataType {
+        [Float FLOAT "float"],
+        [Vec2 FLOAT_VEC2 "vec2"],
+        [Vec3 FLOAT_VEC3 "vec3"],
+        [Vec4 FLOAT_VEC4 "vec4"],
+        [Double DOUBLE "double"],
+        [DVec2 DOUBLE_VEC2 "dvec2"],
+        [DVec3 DOUBLE_VEC3 "dvec3"],
+        [DVec4 DOUBLE_VEC4 "dvec4"],
+        [Int INT "int"],
+        [IVec2 INT_VEC2 "ivec2"],
+        [IVec3 INT_VEC3 "ivec3"],
+        [IVec4 INT_VEC4 "ivec4"],
+        [UInt UNSIGNED_INT "uint"],
+        [UVec2 UNSIGNED_INT_VEC2 "uvec2"],
+        [UVec3 UNSIGNED_INT_VEC3 "uvec3"],
+        [UVec4 UNSIGNED_INT_VEC4 "uvec4"],
+        [Bool BOOL "bool"],
+        [BVec2 BOOL_VEC2 "bvec2"],
+        [BVec3 BOOL_VEC3 "bvec3"],
+        [BVec4 BOOL_VEC4 "bvec4"],
+        [Mat2 FLOAT_MAT2 "mat2"],
+        [Mat3 FLOAT_MAT3 "mat3"],
+        [Mat4 FLOAT_MAT4 "mat4"],
+        [Mat2x3 FLOAT_MAT2x3 "mat2x3"],
+        [Mat2x4 FLOAT_MAT2x4 "mat2x4"],
+        [Mat3x2 FLOAT_MAT3x2 "mat3x2"],
+        [Mat3x4 FLOAT_MAT3x4 "mat3x4"],
+        [Mat4x2 FLOAT_MAT4x2 "mat4x2"],
+        [Mat4x3 FLOAT_MAT4x3 "mat4x3"],
+        [DMat2 DOUBLE_MAT2 "dmat2"],
+        [DMat3 DOUBLE_MAT3 "dmat3"],
+        [DMat4 DOUBLE_MAT4 "dmat4"],
+        [DMat2x3 DOUBLE_MAT2x3 "dmat2x3"],
+        [DMat2x4 DOUBLE_MAT2x4 "dmat2x4"],
+        [DMat3x2 DOUBLE_MAT3x2 "dmat3x2"],
+        [DMat3x4 DOUBLE_MAT3x4 "dmat3x4"],
+        [DMat4x2 DOUBLE_MAT4x2 "dmat4x2"],
+        [DMat4x3 DOUBLE_MAT4x3 "dmat4x3"],
+        [Sampler1D SAMPLER_1D "sampler1D"],
+        [Sampler2D SAMPLER_2D "sampler2D"],
+        [Sampler3D SAMPLER_3D "sampler3D"],
+        [SamplerCube SAMPLER_CUBE "samplerCube"],
+        [Sampler2DRect SAMPLER_2D_RECT "sampler2DRect"],
+        [Sampler1DArray SAMPLER_1D_ARRAY "sampler1DArray"],
+        [Sampler2DArray SAMPLER_2D_ARRAY "sampler2DArray"],
+        [SamplerCubeArray SAMPLER_CUBE_MAP_ARRAY "samplerCubeArray"],
+        [SamplerBuffer SAMPLER_BUFFER "samplerBuffer"],
+        [Sampler2DMS SAMPLER_2D_MULTISAMPLE "sampler2DMS"],
+        [Sampler2DMSArray SAMPLER_2D_MULTISAMPLE_ARRAY "sampler2DMSArray"],
+        [ISampler1D INT_SAMPLER_1D "isampler1D"],
+        [ISampler2D INT_SAMPLER_2D "isampler2D"],
+        [ISampler3D INT_SAMPLER_3D "isampler3D"],
+        [ISamplerCube INT_SAMPLER_CUBE "isamplerCube"],
+        [ISampler2DRect INT_SAMPLER_2D_RECT "isampler2DRect"],
+        [ISampler1DArray INT_SAMPLER_1D_ARRAY "isampler1DArray"],
+        [ISampler2DArray INT_SAMPLER_2D_ARRAY "isampler2DArray"],
+        [ISamplerCubeArray INT_SAMPLER_CUBE_MAP_ARRAY "isamplerCubeArray"],
+        [ISamplerBuffer INT_SAMPLER_BUFFER "isamplerBuffer"],
+        [ISampler2DMS INT_SAMPLER_2D_MULTISAMPLE "isampler2DMS"],
+        [ISampler2DMSArray INT_SAMPLER_2D_MULTISAMPLE_ARRAY "isampler2DMSArray"],
+        [USampler1D UNSIGNED_INT_SAMPLER_1D "usampler1D"],
+        [USampler2D UNSIGNED_INT_SAMPLER_2D "usampler2D"],
+        [USampler3D UNSIGNED_INT_SAMPLER_3D "usampler3D"],
+        [USamplerCube UNSIGNED_INT_SAMPLER_CUBE "usamplerCube"],
+        [USampler2DRect UNSIGNED_INT_SAMPLER_2D_RECT "usampler2DRect"],
+        [USampler1DArray UNSIGNED_INT_SAMPLER_1D_ARRAY "usampler1DArray"],
+        [USampler2DArray UNSIGNED_INT_SAMPLER_2D_ARRAY "usampler2DArray"],
+        [USamplerCubeArray UNSIGNED_INT_SAMPLER_CUBE_MAP_ARRAY "usamplerCubeArray"],
+        [USamplerBuffer UNSIGNED_INT_SAMPLER_BUFFER "usamplerBuffer"],
+        [USampler2DMS UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE "usampler2DMS"],
+        [USampler2DMSArray UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE_ARRAY "usampler2DMSArray"],
+        [Sampler1DShadow SAMPLER_1D_SHADOW "sampler1DShadow"],
+        [Sampler2DShadow SAMPLER_2D_SHADOW "sampler2DShadow"],
+        [SamplerCubeShadow SAMPLER_CUBE_SHADOW "samplerCubeShadow"],
+        [Sampler2DRectShadow SAMPLER_2D_RECT_SHADOW "sampler2DRectShadow"],
+        [Sampler1DArrayShadow SAMPLER_1D_ARRAY_SHADOW "sampler1DArrayShadow"],
+        [Sampler2DArrayShadow SAMPLER_2D_ARRAY_SHADOW "sampler2DArrayShadow"],
+        [SamplerCubeArrayShadow SAMPLER_CUBE_MAP_ARRAY_SHADOW "samplerCubeArrayShadow"],
+        [Image1D IMAGE_1D "image1D"],
+        [Image2D IMAGE_2D "image2D"],
+        [Image3D IMAGE_3D "image3D"],
+        [ImageCube IMAGE_CUBE "imageCube"],
+        [Image2DRect IMAGE_2D_RECT "image2DRect"],
+        [Image1DArray IMAGE_1D_ARRAY "image1DArray"],
+        [Image2DArray IMAGE_2D_ARRAY "image2DArray"],
+        [ImageCubeArray IMAGE_CUBE_MAP_ARRAY "imageCubeArray"],
+        [ImageBuffer IMAGE_BUFFER "imageBuffer"],
+        [Image2DMS IMAGE_2D_MULTISAMPLE "image2DMS"],
+        [Image2DMSArray IMAGE_2D_MULTISAMPLE_ARRAY "image2DMSArray"],
+        [IImage1D INT_IMAGE_1D "iimage1D"],
+        [IImage2D INT_IMAGE_2D "iimage2D"],
+        [IImage3D INT_IMAGE_3D "iimage3D"],
+        [IImageCube INT_IMAGE_CUBE "iimageCube"],
+        [IImage2DRect INT_IMAGE_2D_RECT "iimage2DRect"],
+        [IImage1DArray INT_IMAGE_1D_ARRAY "iimage1DArray"],
+        [IImage2DArray INT_IMAGE_2D_ARRAY "iimage2DArray"],
+        [IImageCubeArray INT_IMAGE_CUBE_MAP_ARRAY "iimageCubeArray"],
+        [IImageBuffer INT_IMAGE_BUFFER "iimageBuffer"],
+        [IImage2DMS INT_IMAGE_2D_MULTISAMPLE "iimage2DMS"],
+        [IImage2DMSArray INT_IMAGE_2D_MULTISAMPLE_ARRAY "iimage2DMSArray"],
+        [UImage1D UNSIGNED_INT_IMAGE_1D "uimage1D"],
+        [UImage2D UNSIGNED_INT_IMAGE_2D "uimage2D"],
+        [UImage3D UNSIGNED_INT_IMAGE_3D "uimage3D"],
+        [UImageCube UNSIGNED_INT_IMAGE_CUBE "uimageCube"],
+        [UImage2DRect UNSIGNED_INT_IMAGE_2D_RECT "uimage2DRect"],
+        [UImage1DArray UNSIGNED_INT_IMAGE_1D_ARRAY "uimage1DArray"],
+        [UImage2DArray UNSIGNED_INT_IMAGE_2D_ARRAY "uimage2DArray"],
+        [UImageCubeArray UNSIGNED_INT_IMAGE_CUBE_MAP_ARRAY "uimageCubeArray"],
+        [UImageBuffer UNSIGNED_INT_IMAGE_BUFFER "uimageBuffer"],
+        [UImage2DMS UNSIGNED_INT_IMAGE_2D_MULTISAMPLE "uimage2DMS"],
+        [UImage2DMSArray UNSIGNED_INT_IMAGE_2D_MULTISAMPLE_ARRAY "uimage2DMSArray"],
+        [AtomicUInt UNSIGNED_INT_ATOMIC_COUNTER "atomic_uint"]
+    }
+}
+
+impl DataType {
+    ///Whether this is a sampler or image type, whose value is the unit its texture is bound to
+    pub fn is_opaque(self) -> bool {
+        let name = self.to_string();
+        name.contains("sampler") || name.contains("image")
+    }
+}
+
+///An active uniform, including those in uniform blocks and atomic counters
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UniformInfo {
+    pub name: String,
+    ///The type of the uniform, or `None` if the driver reported a type not in [DataType]
+    pub ty: Option<DataType>,
+    pub array_size: GLint,
+    ///The location of the uniform or -1 if it is in a block or is an atomic counter
+    pub location: GLint,
+    ///The index into [ProgramInterface::uniform_blocks] or -1 if the uniform isn't in a block
+    pub block_index: GLint,
+    pub offset: GLint,
+    pub array_stride: GLint,
+    pub matrix_stride: GLint,
+    pub row_major: bool,
+    ///The index into [ProgramInterface::atomic_counter_buffers] or -1 if not an atomic counter
+    pub atomic_counter_buffer_index: GLint
+}
+
+///An active vertex input or fragment output
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VariableInfo {
+    pub name: String,
+    pub ty: Option<DataType>,
+    pub array_size: GLint,
+    pub location: GLint,
+    ///The index used for dual-source blending, or -1 if not a fragment output
+    pub location_index: GLint
+}
+
+///A member of a shader storage block
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BufferVariableInfo {
+    pub name: String,
+    pub ty: Option<DataType>,
+    ///The array size, or 0 for the unsized array at the end of a block
+    pub array_size: GLint,
+    pub block_index: GLint,
+    pub offset: GLint,
+    pub array_stride: GLint,
+    pub matrix_stride: GLint,
+    pub row_major: bool,
+    pub top_level_array_size: GLint,
+    pub top_level_array_stride: GLint
+}
+
+///A uniform or shader storage block
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BlockInfo {
+    pub name: String,
+    pub binding: GLuint,
+    pub data_size: usize,
+    ///Indices of the members in [ProgramInterface::uniforms] or [ProgramInterface::buffer_variables]
+    pub members: Vec<usize>
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AtomicCounterBufferInfo {
+    pub binding: GLuint,
+    pub data_size: usize,
+    ///Indices of the counters in [ProgramInterface::uniforms]
+    pub counters: Vec<usize>
+}
+
+///A function that can be chosen for a subroutine uniform
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SubroutineInfo {
+    pub name: String,
+    pub stage: ShaderType,
+    pub index: GLuint
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SubroutineUniformInfo {
+    pub name: String,
+    pub stage: ShaderType,
+    pub array_size: GLint,
+    pub location: GLint,
+    ///The indices of the subroutines of the same stage that can be assigned to this uniform
+    pub compatible: Vec<GLuint>
+}
+
+///
+///Everything the driver reports about the interface of a linked program
+///
+///See [ProgramID::reflect]
+///
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ProgramInterface {
+    pub uniforms: Vec<UniformInfo>,
+    pub attributes: Vec<VariableInfo>,
+    pub outputs: Vec<VariableInfo>,
+    pub uniform_blocks: Vec<BlockInfo>,
+    pub buffer_variables: Vec<BufferVariableInfo>,
+    pub storage_blocks: Vec<BlockInfo>,
+    pub atomic_counter_buffers: Vec<AtomicCounterBufferInfo>,
+    pub subroutines: Vec<SubroutineInfo>,
+    pub subroutine_uniforms: Vec<SubroutineUniformInfo>,
+    ///The local size of the compute shader, if there is one
+    pub work_group_size: Option<[GLuint; 3]>
+}
+
+impl ProgramInterface {
+    #[inline] pub fn uniform(&self, name: &str) -> Option<&UniformInfo> { self.uniforms.iter().find(|u| u.name==name) }
+    #[inline] pub fn attribute(&self, name: &str) -> Option<&VariableInfo> { self.attributes.iter().find(|a| a.name==name) }
+    #[inline] pub fn output(&self, name: &str) -> Option<&VariableInfo> { self.outputs.iter().find(|o| o.name==name) }
+    #[inline] pub fn uniform_block(&self, name: &str) -> Option<&BlockInfo> { self.uniform_blocks.iter().find(|b| b.name==name) }
+    #[inline] pub fn storage_block(&self, name: &str) -> Option<&BlockInfo> { self.storage_blocks.iter().find(|b| b.name==name) }
+}
+
+//the stage of each subroutine and subroutine uniform interface
+const SUBROUTINE_INTERFACES: [(ShaderType, GLenum, GLenum); 6] = [
+    (ShaderType::Vertex, gl::VERTEX_SUBROUTINE, gl::VERTEX_SUBROUTINE_UNIFORM),
+    (ShaderType::TessControl, gl::TESS_CONTROL_SUBROUTINE, gl::TESS_CONTROL_SUBROUTINE_UNIFORM),
+    (ShaderType::TessEval, gl::TESS_EVALUATION_SUBROUTINE, gl::TESS_EVALUATION_SUBROUTINE_UNIFORM),
+    (ShaderType::Geometry, gl::GEOMETRY_SUBROUTINE, gl::GEOMETRY_SUBROUTINE_UNIFORM),
+    (ShaderType::Fragment, gl::FRAGMENT_SUBROUTINE, gl::FRAGMENT_SUBROUTINE_UNIFORM),
+    (ShaderType::Compute, gl::COMPUTE_SUBROUTINE, gl::COMPUTE_SUBROUTINE_UNIFORM),
+];
+
+impl ProgramID {
+
+    ///
+    ///Queries the driver for all of the active resources of this program
+    ///
+    ///Requires GL 4.3 or `ARB_program_interface_query`
+    ///
+    pub fn reflect(&self) -> ProgramInterface {
+        unsafe {
+            let ty = |t: GLint| DataType::try_from(t as GLenum).ok();
+
+            let uniforms = self.resources(gl::UNIFORM, &[
+                gl::TYPE, gl::ARRAY_SIZE, gl::LOCATION, gl::BLOCK_INDEX, gl::OFFSET, gl::ARRAY_STRIDE,
+                gl::MATRIX_STRIDE, gl::IS_ROW_MAJOR, gl::ATOMIC_COUNTER_BUFFER_INDEX
+            ]).into_iter().map(|(name, p)| UniformInfo {
+                name: name, ty: ty(p[0]), array_size: p[1], location: p[2], block_index: p[3], offset: p[4],
+                array_stride: p[5], matrix_stride: p[6], row_major: p[7] != 0, atomic_counter_buffer_index: p[8]
+            }).collect();
+
+            let variables = |interface| self.resources(interface, &[gl::TYPE, gl::ARRAY_SIZE, gl::LOCATION]).into_iter().map(
+                |(name, p)| VariableInfo { name: name, ty: ty(p[0]), array_size: p[1], location: p[2], location_index: -1 }
+            ).collect::<Vec<_>>();
+
+            let mut outputs = variables(gl::PROGRAM_OUTPUT);
+            for output in outputs.iter_mut() {
+                output.location_index = gl::GetProgramResourceLocationIndex(
+                    self.id(), gl::PROGRAM_OUTPUT, CString::new(output.name.as_str()).unwrap().as_ptr()
+                );
+            }
+
+            let buffer_variables = self.resources(gl::BUFFER_VARIABLE, &[
+                gl::TYPE, gl::ARRAY_SIZE, gl::BLOCK_INDEX, gl::OFFSET, gl::ARRAY_STRIDE, gl::MATRIX_STRIDE,
+                gl::IS_ROW_MAJOR, gl::TOP_LEVEL_ARRAY_SIZE, gl::TOP_LEVEL_ARRAY_STRIDE
+            ]).into_iter().map(|(name, p)| BufferVariableInfo {
+                name: name, ty: ty(p[0]), array_size: p[1], block_index: p[2], offset: p[3], array_stride: p[4],
+                matrix_stride: p[5], row_major: p[6] != 0, top_level_array_size: p[7], top_level_array_stride: p[8]
+            }).collect();
+
+            let blocks = |interface| self.resources(interface, &[gl::BUFFER_BINDING, gl::BUFFER_DATA_SIZE]).into_iter().enumerate().map(
+                |(i, (name, p))| BlockInfo {
+                    name: name, binding: p[0] as GLuint, data_size: p[1] as usize,
+                    members: self.active_variables(interface, i as GLuint)
+                }
+            ).collect::<Vec<_>>();
+
+            let atomic_counter_buffers = (0..self.resource_count(gl::ATOMIC_COUNTER_BUFFER)).map(|i| {
+                let p = self.resource_props(gl::ATOMIC_COUNTER_BUFFER, i, &[gl::BUFFER_BINDING, gl::BUFFER_DATA_SIZE]);
+                AtomicCounterBufferInfo {
+                    binding: p[0] as GLuint, data_size: p[1] as usize,
+                    counters: self.active_variables(gl::ATOMIC_COUNTER_BUFFER, i)
+                }
+            }).collect();
+
+            let mut subroutines = Vec::new();
+            let mut subroutine_uniforms = Vec::new();
+            for &(stage, routines, uniforms) in SUBROUTINE_INTERFACES.iter() {
+                for i in 0..self.resource_count(routines) {
+                    subroutines.push(SubroutineInfo { name: self.resource_name(routines, i), stage: stage, index: i });
+                }
+
+                for i in 0..self.resource_count(uniforms) {
+                    let p = self.resource_props(uniforms, i, &[gl::ARRAY_SIZE, gl::LOCATION, gl::NUM_COMPATIBLE_SUBROUTINES]);
+                    let mut compatible = vec![0 as GLint; p[2].max(0) as usize];
+                    if compatible.len() > 0 {
+                        let prop = gl::COMPATIBLE_SUBROUTINES;
+                        gl::GetProgramResourceiv(
+                            self.id(), uniforms, i, 1, &prop, compatible.len() as GLsizei,
+                            ::std::ptr::null_mut(), compatible.as_mut_ptr()
+                        );
+                    }
+                    subroutine_uniforms.push(SubroutineUniformInfo {
+                        name: self.resource_name(uniforms, i), stage: stage, array_size: p[0], location: p[1],
+                        compatible: compatible.into_iter().map(|c| c as GLuint).collect()
+                    });
+                }
+            }
+
+            let mut interface = ProgramInterface {
+                uniforms: uniforms,
+                attributes: variables(gl::PROGRAM_INPUT),
+                outputs: outputs,
+                uniform_blocks: blocks(gl::UNIFORM_BLOCK),
+                buffer_variables: buffer_variables,
+                storage_blocks: blocks(gl::SHADER_STORAGE_BLOCK),
+                atomic_counter_buffers: atomic_counter_buffers,
+                subroutines: subroutines,
+                subroutine_uniforms: subroutine_uniforms,
+                work_group_size: None
+            };
+
+            //querying the work group size of a program without a compute shader is an error
+            if self.stages().contains(&ShaderType::Compute) {
+                let mut size = [0 as GLint; 3];
+                gl::GetProgramiv(self.id(), gl::COMPUTE_WORK_GROUP_SIZE, size.as_mut_ptr());
+                interface.work_group_size = Some([size[0] as GLuint, size[1] as GLuint, size[2] as GLuint]);
+            }
+
+            interface
+        }
+    }
+
+    unsafe fn resource_count(&self, interface: GLenum) -> GLuint {
+        let mut count: GLint = 0;
+        gl::GetProgramInterfaceiv(self.id(), interface, gl::ACTIVE_RESOURCES, &mut count);
+        count as GLuint
+    }
+
+    unsafe fn resource_props(&self, interface: GLenum, index: GLuint, props: &[GLenum]) -> Vec<GLint> {
+        let mut values = vec![0; props.len()];
+        gl::GetProgramResourceiv(
+            self.id(), interface, index, props.len() as GLsizei, props.as_ptr(),
+            values.len() as GLsizei, ::std::ptr::null_mut(), values.as_mut_ptr()
+        );
+        values
+    }
+
+    unsafe fn resource_name(&self, interface: GLenum, index: GLuint) -> String {
+        let len = self.resource_props(interface, index, &[gl::NAME_LENGTH])[0];
+        if len <= 0 { return String::new(); }
+
+        let mut name = vec![0u8; len as usize];
+        let mut actual: GLsizei = 0;
+        gl::GetProgramResourceName(self.id(), interface, index, len, &mut actual, name.as_mut_ptr() as *mut GLchar);
+        name.truncate(actual as usize);
+        String::from_utf8(name).expect("Malformatted resource name")
+    }
+
+    //the name and the given properties of each of the resources of an interface
+    unsafe fn resources(&self, interface: GLenum, props: &[GLenum]) -> Vec<(String, Vec<GLint>)> {
+        (0..self.resource_count(interface)).map(
+            |i| (self.resource_name(interface, i), self.resource_props(interface, i, props))
+        ).collect()
+    }
+
+    unsafe fn active_variables(&self, interface: GLenum, index: GLuint) -> Vec<usize> {
+        let count = self.resource_props(interface, index, &[gl::NUM_ACTIVE_VARIABLES])[0];
+        let mut vars = vec![0 as GLint; count.max(0) as usize];
+        if vars.len() > 0 {
+            let prop = gl::ACTIVE_VARIABLES;
+            gl::GetProgramResourceiv(
+                self.id(), interface, index, 1, &prop, vars.len() as GLsizei, ::std::ptr::null_mut(), vars.as_mut_ptr()
+            );
+        }
+        vars.into_iter().map(|v| v as usize).collect()
+    }
+
+}
@@ -0,0 +1,184 @@
+use super::*;
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+struct ShaderFile {
+    path: PathBuf,
+    ty: ShaderType,
+    modified: Option<SystemTime>
+}
+
+impl ShaderFile {
+    #[inline] fn modified(&self) -> Option<SystemTime> { fs::metadata(&self.path).and_then(|m| m.modified()).ok() }
+
+    fn read(&self) -> Result<String, GLError> {
+        fs::read_to_string(&self.path).map_err(
+            |e| GLError::InvalidOperation(format!("Could not read {} {}: {}", self.ty, self.path.display(), e))
+        )
+    }
+}
+
+//a uniform value with its type erased so it can be moved over to each new program
+pub(crate) trait NamedUniform {
+    unsafe fn relocate(&mut self, p: &ProgramID, name: &str);
+    unsafe fn load(&self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+pub(crate) struct UniformEntry<T:GLSLType> {
+    pub(crate) location: UniformLocation,
+    pub(crate) value: Uniform<T>
+}
+
+impl<T:GLSLType+'static> NamedUniform for UniformEntry<T> {
+    unsafe fn relocate(&mut self, p: &ProgramID, name: &str) {
+        //since the location belongs to the new program, the value is reloaded on the next use
+        self.location = match UniformLocation::get(p, &T::first_element_name(name.to_owned())) {
+            Ok(loc) => loc,
+            Err(loc) => loc
+        };
+    }
+
+    #[inline] unsafe fn load(&self) { self.location.load(&self.value) }
+    #[inline] fn as_any(&self) -> &dyn Any { self }
+    #[inline] fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum BlockKind { Uniform, ShaderStorage }
+
+///
+///A program loaded from shader files that can be recompiled whenever those files change
+///
+///This is meant for iterating on shaders during development without having to rebuild, so
+///the files are simply checked for a new modification time on each call to [reload](Self::reload).
+///If a new version fails to compile, the previous program is kept and the error returned so it
+///can be reported.
+///
+///Since the uniforms and blocks of the program are only known at runtime, they are accessed by name,
+///and any values or bindings set through this object get carried over to each new version.
+///
+pub struct ReloadableProgram {
+    files: Vec<ShaderFile>,
+    program: ProgramID,
+    uniforms: HashMap<String, Box<dyn NamedUniform>>,
+    blocks: HashMap<String, (BlockKind, GLuint)>
+}
+
+impl ReloadableProgram {
+
+    pub fn new<P:AsRef<Path>>(gl: &GLProvider, files: &[(P, ShaderType)]) -> Result<Self, GLError> {
+        let files: Vec<ShaderFile> = files.iter().map(
+            |(path, ty)| ShaderFile { path: path.as_ref().to_owned(), ty: *ty, modified: None }
+        ).collect();
+
+        let mut program = ReloadableProgram {
+            program: Self::compile(gl, &files)?,
+            files: files,
+            uniforms: HashMap::new(),
+            blocks: HashMap::new()
+        };
+
+        for file in program.files.iter_mut() { file.modified = file.modified(); }
+        Ok(program)
+    }
+
+    fn compile(gl: &GLProvider, files: &[ShaderFile]) -> Result<ProgramID, GLError> {
+        let mut sources = Vec::with_capacity(files.len());
+        for file in files.iter() { sources.push(file.read()?); }
+        ProgramID::from_source(gl, sources.iter().zip(files.iter()).map(|(src, f)| (&src[..], f.ty)).collect())
+    }
+
+    ///Checks if any of the source files have changed since the last compile
+    pub fn is_outdated(&self) -> bool {
+        //files that are missing (like in the middle of being saved) are ignored until they come back
+        self.files.iter().any(|f| match f.modified() { Some(t) => Some(t) != f.modified, None => false })
+    }
+
+    ///
+    ///Recompiles the program if any of its files changed, returning whether or not it did
+    ///
+    ///On failure, the current program is kept as is, and the program won't be compiled again
+    ///until one of the files changes again.
+    ///
+    pub fn reload(&mut self, gl: &GLProvider) -> Result<bool, GLError> {
+        if !self.is_outdated() { return Ok(false); }
+
+        for file in self.files.iter_mut() { file.modified = file.modified(); }
+        self.program = Self::compile(gl, &self.files)?;
+
+        unsafe {
+            for (name, uniform) in self.uniforms.iter_mut() {
+                uniform.relocate(&self.program, name);
+            }
+
+            for (name, (kind, binding)) in self.blocks.iter() {
+                Self::apply_binding(&self.program, name, *kind, *binding);
+            }
+        }
+
+        Ok(true)
+    }
+
+    ///
+    ///Gets the value of the uniform with the given name, starting with the value in the program
+    ///the first time it is requested
+    ///
+    ///Returns `None` if the uniform was previously requested as a different type.
+    ///
+    pub fn uniform<T:GLSLType+'static>(&mut self, name: &str) -> Option<&mut Uniform<T>> {
+        let program = &self.program;
+        let entry = self.uniforms.entry(name.to_owned()).or_insert_with(|| unsafe {
+            let location = match UniformLocation::get(program, &T::first_element_name(name.to_owned())) {
+                Ok(loc) => loc,
+                Err(loc) => loc
+            };
+            let value = location.get_uniform::<T>();
+            Box::new(UniformEntry { location, value })
+        });
+
+        entry.as_any_mut().downcast_mut::<UniformEntry<T>>().map(|e| &mut e.value)
+    }
+
+    ///Sets the binding index of the uniform block with the given name
+    pub fn set_uniform_block_binding(&mut self, name: &str, binding: GLuint) {
+        debug_assert!(binding < gl::MAX_UNIFORM_BUFFER_BINDINGS, "UBO Binding higher than maximum!");
+        unsafe { Self::apply_binding(&self.program, name, BlockKind::Uniform, binding); }
+        self.blocks.insert(name.to_owned(), (BlockKind::Uniform, binding));
+    }
+
+    ///Sets the binding index of the shader storage block with the given name
+    pub fn set_storage_block_binding(&mut self, name: &str, binding: GLuint) {
+        unsafe { Self::apply_binding(&self.program, name, BlockKind::ShaderStorage, binding); }
+        self.blocks.insert(name.to_owned(), (BlockKind::ShaderStorage, binding));
+    }
+
+    unsafe fn apply_binding(p: &ProgramID, name: &str, kind: BlockKind, binding: GLuint) {
+        let name = CString::new(name).unwrap();
+        match kind {
+            BlockKind::Uniform => {
+                let id = gl::GetUniformBlockIndex(p.id(), name.as_ptr());
+                if id != gl::INVALID_INDEX { gl::UniformBlockBinding(p.id(), id, binding); }
+            },
+            BlockKind::ShaderStorage => {
+                let id = gl::GetProgramResourceIndex(p.id(), gl::SHADER_STORAGE_BLOCK, name.as_ptr());
+                if id != gl::INVALID_INDEX { gl::ShaderStorageBlockBinding(p.id(), id, binding); }
+            }
+        }
+    }
+
+    ///Binds the current version of the program and loads any uniforms that have changed
+    pub unsafe fn use_program(&self) {
+        self.program.use_program();
+        for uniform in self.uniforms.values() { uniform.load(); }
+    }
+
+    #[inline] pub fn program(&self) -> &ProgramID { &self.program }
+
+}
@@ -0,0 +1,269 @@
+use super::*;
+
+use std::ffi::CString;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+//GL 4.6 and ARB_gl_spirv aren't part of the generated bindings, so these are loaded separately
+pub const SHADER_BINARY_FORMAT_SPIR_V: GLenum = 0x9551;
+
+type SpecializeShaderFn = extern "system" fn(GLuint, *const GLchar, GLuint, *const GLuint, *const GLuint);
+
+static SPECIALIZE_SHADER: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn load_with<F: FnMut(&'static str) -> *const GLvoid>(mut proc_addr: F) {
+    let mut ptr = proc_addr("glSpecializeShader");
+    if ptr.is_null() { ptr = proc_addr("glSpecializeShaderARB"); }
+    SPECIALIZE_SHADER.store(ptr as usize, Ordering::Relaxed);
+}
+
+fn specialize_shader() -> Result<SpecializeShaderFn, GLError> {
+    match SPECIALIZE_SHADER.load(Ordering::Relaxed) {
+        0 => Err(GLError::FunctionNotLoaded("glSpecializeShader")),
+        ptr => Ok(unsafe { ::std::mem::transmute::<usize, SpecializeShaderFn>(ptr) })
+    }
+}
+
+const MAGIC: u32 = 0x07230203;
+
+const OP_ENTRY_POINT: u32 = 15;
+const OP_DECORATE: u32 = 71;
+const DECORATION_SPEC_ID: u32 = 1;
+
+///A function that a [SpirVModule] can be specialized with
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EntryPoint {
+    pub name: String,
+    ///The stage of the entry point, or `None` if it's an OpenCL kernel
+    pub stage: Option<ShaderType>
+}
+
+///
+///The parts of a SPIR-V module that are checked before handing it to the driver
+///
+///The module may be in either byte order, as determined from its magic number.
+///
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SpirVModule {
+    pub version: (u8, u8),
+    pub entry_points: Vec<EntryPoint>,
+    ///The `SpecId` of every specialization constant
+    pub spec_constants: Vec<GLuint>
+}
+
+impl SpirVModule {
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, GLError> {
+        let err = |msg: String| Err(GLError::InvalidSpirV(msg));
+
+        if bytes.len() % 4 != 0 {
+            return err(format!("Module size of {} bytes is not a whole number of words", bytes.len()));
+        }
+        if bytes.len() < 20 {
+            return err("Module is too short for a SPIR-V header".to_owned());
+        }
+
+        let le = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) == MAGIC;
+        let be = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) == MAGIC;
+        if !le && !be { return err("Missing the SPIR-V magic number".to_owned()); }
+
+        let words: Vec<u32> = bytes.chunks(4).map(
+            |w| if le { u32::from_le_bytes([w[0], w[1], w[2], w[3]]) } else { u32::from_be_bytes([w[0], w[1], w[2], w[3]]) }
+        ).collect();
+
+        let version = ((words[1] >> 16) as u8, (words[1] >> 8) as u8);
+        if version.0 != 1 || words[1] & 0xFF0000FF != 0 {
+            return err(format!("Unsupported SPIR-V version word {:#010x}", words[1]));
+        }
+        if words[3] == 0 { return err("The id bound of the module is zero".to_owned()); }
+        if words[4] != 0 { return err(format!("Unknown instruction schema {}", words[4])); }
+
+        let mut module = SpirVModule { version: version, entry_points: Vec::new(), spec_constants: Vec::new() };
+
+        let mut i = 5;
+        while i < words.len() {
+            let count = (words[i] >> 16) as usize;
+            let opcode = words[i] & 0xFFFF;
+            if count == 0 || i + count > words.len() {
+                return err(format!("Instruction at word {} has an invalid word count of {}", i, count));
+            }
+            let operands = &words[i+1 .. i+count];
+
+            match opcode {
+                OP_ENTRY_POINT => {
+                    let name = match operands.get(2..).and_then(literal_string) {
+                        Some(name) => name,
+                        None => return err(format!("Entry point at word {} is missing its name", i))
+                    };
+                    module.entry_points.push(EntryPoint { name: name, stage: execution_model(operands[0]) });
+                },
+                OP_DECORATE if operands.get(1) == Some(&DECORATION_SPEC_ID) => {
+                    match operands.get(2) {
+                        Some(&id) => module.spec_constants.push(id),
+                        None => return err(format!("SpecId decoration at word {} is missing its id", i))
+                    }
+                },
+                _ => ()
+            }
+
+            i += count;
+        }
+
+        Ok(module)
+    }
+
+    ///Finds the entry point with the given name and stage
+    pub fn entry_point(&self, name: &str, stage: ShaderType) -> Option<&EntryPoint> {
+        self.entry_points.iter().find(|e| e.name == name && e.stage == Some(stage))
+    }
+
+    //checks that the module can be specialized with the arguments given to Shader::from_spirv
+    fn check_specialization(
+        &self, ty: ShaderType, entry_point: &str, constants: &[(GLuint, GLuint)]
+    ) -> Result<(), GLError> {
+        if self.entry_point(entry_point, ty).is_none() {
+            let names: Vec<&str> = self.entry_points.iter().filter(|e| e.stage == Some(ty)).map(|e| e.name.as_str()).collect();
+            return Err(GLError::InvalidSpirV(format!(
+                "No {} entry point named \"{}\" (found: {:?})", ty, entry_point, names
+            )));
+        }
+
+        for (id, _) in constants.iter() {
+            if !self.spec_constants.contains(id) {
+                return Err(GLError::InvalidSpirV(format!("No specialization constant with id {}", id)));
+            }
+        }
+
+        Ok(())
+    }
+
+}
+
+//the execution models that can be used with GL
+fn execution_model(model: u32) -> Option<ShaderType> {
+    match model {
+        0 => Some(ShaderType::Vertex),
+        1 => Some(ShaderType::TessControl),
+        2 => Some(ShaderType::TessEval),
+        3 => Some(ShaderType::Geometry),
+        4 => Some(ShaderType::Fragment),
+        5 => Some(ShaderType::Compute),
+        _ => None
+    }
+}
+
+//strings are packed into words as null-terminated UTF-8 with the first byte in the lowest bits
+fn literal_string(words: &[u32]) -> Option<String> {
+    let mut bytes = Vec::with_capacity(words.len() * 4);
+    for w in words.iter() { bytes.extend_from_slice(&w.to_le_bytes()); }
+    let end = bytes.iter().position(|&b| b == 0)?;
+    bytes.truncate(end);
+    String::from_utf8(bytes).ok()
+}
+
+impl Shader {
+
+    ///
+    ///Creates a shader from a SPIR-V module, specialized for the given entry point and with each
+    ///of the given specialization constants (by `constant_id`) set to a value
+    ///
+    ///The values are the raw 32-bit contents of each constant, so floats should be passed with
+    ///[f32::to_bits]. The module header, the entry point, and the constant ids are all checked
+    ///before the module is given to the driver.
+    ///
+    ///Note that GL doesn't allow SPIR-V and GLSL shaders to be linked into the same program, but
+    ///separable programs of either kind can still be mixed in a [ProgramPipeline].
+    ///
+    ///Requires GL 4.6 or `ARB_gl_spirv`
+    ///
+    pub fn from_spirv(
+        _gl: &GLProvider, bytes: &[u8], ty: ShaderType, entry_point: &str, constants: &[(GLuint, GLuint)]
+    ) -> Result<Self, GLError> {
+
+        SpirVModule::parse(bytes)?.check_specialization(ty, entry_point, constants)?;
+
+        let specialize = specialize_shader()?;
+        if !gl::ShaderBinary::is_loaded() { return Err(GLError::FunctionNotLoaded("glShaderBinary")); }
+
+        unsafe {
+            let s = Shader::from_raw(gl::CreateShader(ty.into()), ty, true);
+
+            gl::ShaderBinary(
+                1, &s.id(), SHADER_BINARY_FORMAT_SPIR_V, bytes.as_ptr() as *const GLvoid, bytes.len() as GLsizei
+            );
+
+            let name = CString::new(entry_point).unwrap();
+            let ids: Vec<GLuint> = constants.iter().map(|c| c.0).collect();
+            let values: Vec<GLuint> = constants.iter().map(|c| c.1).collect();
+            specialize(s.id(), name.as_ptr(), constants.len() as GLuint, ids.as_ptr(), values.as_ptr());
+
+            if s.get_shader_int(gl::COMPILE_STATUS) == gl::FALSE as GLint {
+                Err(GLError::ShaderCompilation(s.id(), ty, s.shader_info_log()))
+            } else {
+                Ok(s)
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a version 1.3 compute module with a "main" entry point and a SpecId of 7
+    fn module() -> Vec<u32> {
+        vec![
+            MAGIC, 0x00010300, 0, 10, 0,
+            (5 << 16) | OP_ENTRY_POINT, 5, 1, u32::from_le_bytes(*b"main"), 0,
+            (2 << 16) | 14, 0,
+            (4 << 16) | OP_DECORATE, 2, DECORATION_SPEC_ID, 7
+        ]
+    }
+
+    fn le(words: &[u32]) -> Vec<u8> { words.iter().flat_map(|w| w.to_le_bytes()).collect() }
+    fn be(words: &[u32]) -> Vec<u8> { words.iter().flat_map(|w| w.to_be_bytes()).collect() }
+
+    #[test]
+    fn both_byte_orders() {
+        let expected = SpirVModule {
+            version: (1, 3),
+            entry_points: vec![EntryPoint { name: "main".to_owned(), stage: Some(ShaderType::Compute) }],
+            spec_constants: vec![7]
+        };
+        assert_eq!(SpirVModule::parse(&le(&module())).unwrap(), expected);
+        assert_eq!(SpirVModule::parse(&be(&module())).unwrap(), expected);
+    }
+
+    #[test]
+    fn bad_header() {
+        let mut words = module();
+        words[0] = 0x12345678;
+        assert!(SpirVModule::parse(&le(&words)).is_err());
+
+        let mut words = module();
+        words[1] = 0x00020000;
+        assert!(SpirVModule::parse(&le(&words)).is_err());
+
+        assert!(SpirVModule::parse(&le(&module()[..4])).is_err());
+        assert!(SpirVModule::parse(&le(&module())[..17]).is_err());
+    }
+
+    #[test]
+    fn bad_instructions() {
+        let words = module();
+        assert!(SpirVModule::parse(&le(&words[..words.len()-1])).is_err());
+
+        let mut words = module();
+        words[10] = 14;
+        assert!(SpirVModule::parse(&le(&words)).is_err());
+    }
+
+    #[test]
+    fn specialization() {
+        let module = SpirVModule::parse(&le(&module())).unwrap();
+        assert!(module.check_specialization(ShaderType::Compute, "main", &[(7, 1)]).is_ok());
+        assert!(module.check_specialization(ShaderType::Compute, "other", &[]).is_err());
+        assert!(module.check_specialization(ShaderType::Vertex, "main", &[]).is_err());
+        assert!(module.check_specialization(ShaderType::Compute, "main", &[(8, 1)]).is_err());
+    }
+}
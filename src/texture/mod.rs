@@ -1,17 +1,12 @@
-#![allow(dead_code)]
-
 use super::*;
 use crate::gl;
 
-use super::Target;
-
-use std::convert::TryInto;
+use std::mem::size_of;
+use std::marker::PhantomData;
 
 pub use self::pixel_format::*;
-pub use self::pixel_data::*;
 
 mod pixel_format;
-mod pixel_data;
 
 glenum! {
     pub enum TextureTarget {
@@ -25,7 +20,7 @@ glenum! {
         [TextureCubeMap TEXTURE_CUBE_MAP "Texture Cube Map"],
         [TextureCubeMapArray TEXTURE_CUBE_MAP_ARRAY "Texture Cube Map Array"],
         [Texture2DMultisample TEXTURE_2D_MULTISAMPLE "Texture 2D Multisample"],
-        [Texture1DMultisampleArray TEXTURE_2D_MULTISAMPLE_ARRAY "Texture 2D Multisample Array"]
+        [Texture2DMultisampleArray TEXTURE_2D_MULTISAMPLE_ARRAY "Texture 2D Multisample Array"]
     }
 }
 
@@ -33,194 +28,473 @@ impl TextureTarget {
     #[inline]
     pub fn multisample(self) -> bool {
         match self {
-            Self::Texture2DMultisample | Self::Texture1DMultisampleArray => true,
+            Self::Texture2DMultisample | Self::Texture2DMultisampleArray => true,
             _ => false
         }
     }
+
+    ///
+    ///The number of dimensions of the storage of this target, counting array layers and cube faces
+    ///as a dimension
+    ///
+    #[inline]
+    pub fn dimensions(self) -> usize {
+        match self {
+            Self::Texture1D | Self::TextureBuffer => 1,
+            Self::Texture2D | Self::Texture1DArray | Self::TextureRectangle |
+            Self::TextureCubeMap | Self::Texture2DMultisample => 2,
+            Self::Texture3D | Self::Texture2DArray | Self::TextureCubeMapArray |
+            Self::Texture2DMultisampleArray => 3
+        }
+    }
 }
 
-unsafe impl Target for TextureTarget {
-    type Resource = RawTex;
-    #[inline] unsafe fn bind(self, id:GLuint) {gl::BindTexture(self as GLenum, id)}
+///
+///Marks the internal formats that can be read with a float sampler (`sampler2D`, etc)
+///
+///This includes depth formats, since sampling those without a comparison just returns the depth.
+///
+pub unsafe trait FloatSampling: InternalFormat {}
+
+///Marks the internal formats that can be read with a signed integer sampler (`isampler2D`, etc)
+pub unsafe trait IntSampling: InternalFormat {}
+
+///Marks the internal formats that can be read with an unsigned integer sampler (`usampler2D`, etc)
+pub unsafe trait UIntSampling: InternalFormat {}
+
+///Marks the internal formats that can be read with a shadow sampler (`sampler2DShadow`, etc)
+pub unsafe trait ShadowSampling: InternalFormat {}
+
+unsafe impl FloatSampling for InternalFormatFloat {}
+unsafe impl FloatSampling for InternalFormatDepth {}
+unsafe impl FloatSampling for InternalFormatDepthStencil {}
+unsafe impl IntSampling for InternalFormatInt {}
+unsafe impl UIntSampling for InternalFormatUInt {}
+unsafe impl UIntSampling for InternalFormatStencil {}
+unsafe impl ShadowSampling for InternalFormatDepth {}
+unsafe impl ShadowSampling for InternalFormatDepthStencil {}
+
+///
+///A texture object with immutable storage of the given internal format
+///
+///The dimensions are always stored as `[width, height, depth]`, where the depth is the number of
+///layers for array textures and the number of cube faces for cube map arrays, and any dimensions
+///the target doesn't have are 1.
+///
+pub struct Texture<F:InternalFormat> {
+    id: GLuint,
+    target: TextureTarget,
+    format: F,
+    dim: [usize; 3],
+    levels: usize
 }
 
+impl<F:InternalFormat> Texture<F> {
 
-gl_resource!{
-    pub struct RawTex {
-        gl = GL2,
-        target = TextureTarget,
-        gen = GenTextures,
-        is = IsTexture,
-        delete = DeleteTextures
+    unsafe fn gen(target: TextureTarget, format: F, levels: usize, dim: [usize; 3]) -> Self {
+        let mut id = 0;
+        gl::GenTextures(1, &mut id as *mut GLuint);
+        Texture { id: id, target: target, format: format, dim: dim, levels: levels }
     }
-}
 
-pub unsafe trait TexDim:Copy {
-    fn dim() -> usize;
-    fn width(&self) -> usize;
-    fn height(&self) -> usize;
-    fn depth(&self) -> usize;
-}
+    ///
+    ///Creates a texture with `levels` mipmap levels of uninitialized storage
+    ///
+    ///Buffer and multisample textures have their own storage and can't be made this way.
+    ///
+    pub fn storage(_gl: &GLProvider, target: TextureTarget, format: F, levels: usize, dim: [usize; 3]) -> Result<Self, GLError> {
+        if target == TextureTarget::TextureBuffer || target.multisample() {
+            return Err(GLError::InvalidOperation(format!("{} textures can't be created with glTexStorage", target)));
+        }
+
+        let dimensions = target.dimensions();
+        let dim = [dim[0], if dimensions > 1 { dim[1] } else { 1 }, if dimensions > 2 { dim[2] } else { 1 }];
+
+        check_loaded!(TexStorage1D, TexStorage2D, TexStorage3D; ())?;
+
+        unsafe {
+            let tex = Self::gen(target, format, levels, dim);
+            gl::BindTexture(target as GLenum, tex.id);
+            match dimensions {
+                1 => gl::TexStorage1D(target as GLenum, levels as GLsizei, format.into(), dim[0] as GLsizei),
+                2 => gl::TexStorage2D(
+                    target as GLenum, levels as GLsizei, format.into(), dim[0] as GLsizei, dim[1] as GLsizei
+                ),
+                _ => gl::TexStorage3D(
+                    target as GLenum, levels as GLsizei, format.into(),
+                    dim[0] as GLsizei, dim[1] as GLsizei, dim[2] as GLsizei
+                )
+            }
+
+            //the default filter reads from mipmaps, which leaves single level textures incomplete
+            if levels == 1 {
+                gl::TexParameteri(target as GLenum, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            }
+
+            gl::BindTexture(target as GLenum, 0);
+            Ok(tex)
+        }
+    }
+
+    ///
+    ///Creates a buffer texture that reads the contents of the given buffer as texels of this format
+    ///
+    ///Note that the texture only refers to the buffer by id, so it doesn't keep it borrowed
+    ///
+    pub fn buffer<T, A:BufferAccess>(_gl: &GLProvider, format: F, buffer: &Buffer<[T], A>) -> Result<Self, GLError> {
+        let size = buffer.data_size();
+        let texel = format_size(format.into());
+
+        unsafe {
+            let tex = Self::gen(TextureTarget::TextureBuffer, format, 1, [size / texel.max(1), 1, 1]);
+            gl::BindTexture(gl::TEXTURE_BUFFER, tex.id);
+            if buffer.data_offset() == 0 {
+                gl::TexBuffer(gl::TEXTURE_BUFFER, format.into(), buffer.id());
+            } else {
+                check_loaded!(TexBufferRange; ())?;
+                gl::TexBufferRange(
+                    gl::TEXTURE_BUFFER, format.into(), buffer.id(),
+                    buffer.data_offset() as GLintptr, size as GLsizeiptr
+                );
+            }
+            gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+            Ok(tex)
+        }
+    }
+
+    #[inline] pub fn id(&self) -> GLuint { self.id }
+    #[inline] pub fn target(&self) -> TextureTarget { self.target }
+    #[inline] pub fn format(&self) -> F { self.format }
+    #[inline] pub fn dim(&self) -> [usize; 3] { self.dim }
+    #[inline] pub fn levels(&self) -> usize { self.levels }
+
+    ///
+    ///Uploads the pixels of a region of the given mipmap level, where the pixels are tightly packed
+    ///and laid out as `format`
+    ///
+    ///For cube maps, the z offset and depth select the faces in the usual
+    ///`+X, -X, +Y, -Y, +Z, -Z` order.
+    ///
+    pub fn sub_image<T:Copy>(
+        &mut self, level: usize, offset: [usize; 3], dim: [usize; 3], format: F::TypeFormat, pixels: &[T]
+    ) {
+        assert!(level < self.levels, "Mipmap level {} out of bounds for {} levels", level, self.levels);
+        assert!(self.target != TextureTarget::TextureBuffer, "Buffer textures are updated through their buffer");
+
+        let dimensions = self.target.dimensions();
+        let faces = if self.target == TextureTarget::TextureCubeMap { 6 } else { 1 };
+        let layered = dimensions > 2 || faces > 1;
+        let dim = [dim[0], if dimensions > 1 { dim[1] } else { 1 }, if layered { dim[2] } else { 1 }];
+
+        //array layers don't get smaller with each mipmap level, but everything else does
+        let mip = |x: usize| (x >> level).max(1);
+        let size = match self.target {
+            TextureTarget::Texture1DArray => [mip(self.dim[0]), self.dim[1], 1],
+            TextureTarget::Texture3D => [mip(self.dim[0]), mip(self.dim[1]), mip(self.dim[2])],
+            TextureTarget::TextureCubeMap => [mip(self.dim[0]), mip(self.dim[1]), faces],
+            _ => [mip(self.dim[0]), if dimensions > 1 { mip(self.dim[1]) } else { 1 }, self.dim[2]]
+        };
+        for i in 0..3 {
+            assert!(offset[i] + dim[i] <= size[i], "Texture region out of bounds: {:?}+{:?} > {:?}", offset, dim, size);
+        }
+
+        let bytes = dim[0] * dim[1] * dim[2] * format.size();
+        assert_eq!(
+            pixels.len() * size_of::<T>(), bytes,
+            "Invalid number of pixels for dimensions {:?}", dim
+        );
+
+        unsafe {
+            let (fmt, ty) = format.format_type();
+            let (fmt, ty) = (fmt.into(), ty.into());
+            let (x, y, z) = (offset[0] as GLint, offset[1] as GLint, offset[2] as GLint);
+            let (w, h, d) = (dim[0] as GLsizei, dim[1] as GLsizei, dim[2] as GLsizei);
+            let data = pixels.as_ptr() as *const GLvoid;
+
+            gl::BindTexture(self.target as GLenum, self.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+            if faces > 1 {
+                //each face of a (non-array) cube map is its own 2D image
+                let face_size = bytes / dim[2].max(1);
+                for face in 0..d {
+                    gl::TexSubImage2D(
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + (z + face) as GLenum, level as GLint, x, y, w, h, fmt, ty,
+                        (data as *const u8).offset((face as usize * face_size) as isize) as *const GLvoid
+                    );
+                }
+            } else {
+                match dimensions {
+                    1 => gl::TexSubImage1D(self.target as GLenum, level as GLint, x, w, fmt, ty, data),
+                    2 => gl::TexSubImage2D(self.target as GLenum, level as GLint, x, y, w, h, fmt, ty, data),
+                    _ => gl::TexSubImage3D(self.target as GLenum, level as GLint, x, y, z, w, h, d, fmt, ty, data)
+                }
+            }
+
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::BindTexture(self.target as GLenum, 0);
+        }
+    }
+
+    ///Fills in every mipmap level after the first from the base image
+    pub fn generate_mipmap(&mut self) {
+        unsafe {
+            gl::BindTexture(self.target as GLenum, self.id);
+            gl::GenerateMipmap(self.target as GLenum);
+            gl::BindTexture(self.target as GLenum, 0);
+        }
+    }
 
-unsafe impl TexDim for [usize;1] {
-    fn dim() -> usize {1}
-    fn width(&self) -> usize {self[0]}
-    fn height(&self) -> usize {0}
-    fn depth(&self) -> usize {0}
 }
 
-unsafe impl TexDim for [usize;2] {
-    fn dim() -> usize {2}
-    fn width(&self) -> usize {self[0]}
-    fn height(&self) -> usize {self[1]}
-    fn depth(&self) -> usize {0}
+impl<F:InternalFormat> Drop for Texture<F> {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteTextures(1, &self.id as *const GLuint); }
+    }
 }
 
-unsafe impl TexDim for [usize;3] {
-    fn dim() -> usize {3}
-    fn width(&self) -> usize {self[0]}
-    fn height(&self) -> usize {self[1]}
-    fn depth(&self) -> usize {self[2]}
+//the size of a texel of the sized formats that can be used with buffer textures and images
+fn format_size(format: GLenum) -> usize {
+    match format {
+        gl::R8 | gl::R8_SNORM | gl::R8I | gl::R8UI => 1,
+        gl::R16 | gl::R16_SNORM | gl::R16F | gl::R16I | gl::R16UI |
+        gl::RG8 | gl::RG8_SNORM | gl::RG8I | gl::RG8UI => 2,
+        gl::RGB8 | gl::RGB8I | gl::RGB8UI => 3,
+        gl::R32F | gl::R32I | gl::R32UI | gl::RG16 | gl::RG16_SNORM | gl::RG16F | gl::RG16I | gl::RG16UI |
+        gl::RGBA8 | gl::RGBA8_SNORM | gl::RGBA8I | gl::RGBA8UI |
+        gl::RGB10_A2 | gl::RGB10_A2UI | gl::R11F_G11F_B10F => 4,
+        gl::RGB16 | gl::RGB16F | gl::RGB16I | gl::RGB16UI => 6,
+        gl::RG32F | gl::RG32I | gl::RG32UI |
+        gl::RGBA16 | gl::RGBA16_SNORM | gl::RGBA16F | gl::RGBA16I | gl::RGBA16UI => 8,
+        gl::RGB32F | gl::RGB32I | gl::RGB32UI => 12,
+        gl::RGBA32F | gl::RGBA32I | gl::RGBA32UI => 16,
+        _ => 0
+    }
 }
 
-unsafe fn apply_packing_settings<F:PixelFormatType,P:PixelData<F>>(pixels:&P) {
-    gl::PixelStorei(gl::PACK_SWAP_BYTES, pixels.swap_bytes() as GLint);
-    gl::PixelStorei(gl::PACK_LSB_FIRST, pixels.lsb_first() as GLint);
-    gl::PixelStorei(gl::PACK_ALIGNMENT, pixels.alignment().0 as GLint);
+glenum! {
+    pub enum MinFilter {
+        [Nearest NEAREST "Nearest"],
+        [Linear LINEAR "Linear"],
+        [NearestMipmapNearest NEAREST_MIPMAP_NEAREST "Nearest Mipmap Nearest"],
+        [LinearMipmapNearest LINEAR_MIPMAP_NEAREST "Linear Mipmap Nearest"],
+        [NearestMipmapLinear NEAREST_MIPMAP_LINEAR "Nearest Mipmap Linear"],
+        [LinearMipmapLinear LINEAR_MIPMAP_LINEAR "Linear Mipmap Linear"]
+    }
+
+    pub enum MagFilter {
+        [Nearest NEAREST "Nearest"],
+        [Linear LINEAR "Linear"]
+    }
+
+    pub enum Wrap {
+        [Repeat REPEAT "Repeat"],
+        [MirroredRepeat MIRRORED_REPEAT "Mirrored Repeat"],
+        [ClampToEdge CLAMP_TO_EDGE "Clamp to Edge"],
+        [ClampToBorder CLAMP_TO_BORDER "Clamp to Border"],
+        [MirrorClampToEdge MIRROR_CLAMP_TO_EDGE "Mirror Clamp to Edge"]
+    }
+
+    pub enum CompareFunc {
+        [Never NEVER "Never"],
+        [Less LESS "Less"],
+        [Equal EQUAL "Equal"],
+        [LEqual LEQUAL "Less or Equal"],
+        [Greater GREATER "Greater"],
+        [NotEqual NOTEQUAL "Not Equal"],
+        [GEqual GEQUAL "Greater or Equal"],
+        [Always ALWAYS "Always"]
+    }
 }
 
-unsafe fn apply_unpacking_settings<F:PixelFormatType,P:PixelData<F>>(pixels:&P) {
-    gl::PixelStorei(gl::UNPACK_SWAP_BYTES, pixels.swap_bytes() as GLint);
-    gl::PixelStorei(gl::UNPACK_LSB_FIRST, pixels.lsb_first() as GLint);
-    gl::PixelStorei(gl::UNPACK_ALIGNMENT, pixels.alignment().0 as GLint);
+///
+///A sampler object, which holds the filtering, wrapping, and comparison settings used when reading
+///a texture in a shader
+///
+///Requires GL 3.3 or `ARB_sampler_objects`
+///
+pub struct Sampler {
+    id: GLuint
 }
 
-pub unsafe trait Texture: Sized {
-    type InternalFormat: InternalFormat<TypeFormat=Self::PixelFormat>;
-    type PixelFormat: PixelFormatType;
-    type Dim: TexDim;
+impl Sampler {
+
+    pub fn new(_gl: &GLProvider) -> Result<Self, GLError> {
+        check_loaded!(GenSamplers, BindSampler, SamplerParameteri; ())?;
+        let mut id = 0;
+        unsafe { gl::GenSamplers(1, &mut id as *mut GLuint); }
+        Ok(Sampler { id: id })
+    }
 
-    fn target() -> TextureTarget;
-    fn id(&self) -> GLuint;
-    fn format(&self) -> Self::InternalFormat;
-    fn dim(&self) -> Self::Dim;
+    #[inline] pub fn id(&self) -> GLuint { self.id }
 
-    fn storage<P:PixelData<Self::PixelFormat>>(
-        _gl:&GL4,
-        raw:RawTex,
-        levels:usize,
-        internalformat:Self::InternalFormat,
-        dim:Self::Dim,
-        _pixels:P
-    ) -> Self
-    {
+    pub fn set_filter(&mut self, min: MinFilter, mag: MagFilter) {
         unsafe {
-            let mut target = Self::target().as_loc();
-            let binding = target.bind(&raw);
-            match Self::Dim::dim() {
-                1 => gl::TexStorage1D(
-                    binding.target_id(),
-                    levels as GLint, internalformat.into() as GLuint,
-                    dim.width() as GLsizei
-                ),
-                2 => gl::TexStorage2D(
-                    binding.target_id(),
-                    levels as GLint, internalformat.into() as GLuint,
-                    dim.width() as GLsizei, dim.height() as GLsizei
-                ),
-                3 => gl::TexStorage3D(
-                    binding.target_id(),
-                    levels as GLint, internalformat.into() as GLuint,
-                    dim.width() as GLsizei, dim.height() as GLsizei, dim.depth() as GLsizei
-                ),
-                _ => panic!("{}D textures not supported", Self::Dim::dim())
-            };
-            ::std::mem::uninitialized()
+            gl::SamplerParameteri(self.id, gl::TEXTURE_MIN_FILTER, min as GLint);
+            gl::SamplerParameteri(self.id, gl::TEXTURE_MAG_FILTER, mag as GLint);
+        }
+    }
+
+    ///Sets the wrapping modes for the `s`, `t`, and `r` texture coordinates respectively
+    pub fn set_wrap(&mut self, s: Wrap, t: Wrap, r: Wrap) {
+        unsafe {
+            gl::SamplerParameteri(self.id, gl::TEXTURE_WRAP_S, s as GLint);
+            gl::SamplerParameteri(self.id, gl::TEXTURE_WRAP_T, t as GLint);
+            gl::SamplerParameteri(self.id, gl::TEXTURE_WRAP_R, r as GLint);
+        }
+    }
+
+    ///
+    ///Sets the depth comparison used by shadow samplers, or disables it with `None`
+    ///
+    ///Note that the results of shadow samplers are undefined if this isn't set
+    ///
+    pub fn set_compare(&mut self, func: Option<CompareFunc>) {
+        unsafe {
+            match func {
+                Some(f) => {
+                    gl::SamplerParameteri(self.id, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as GLint);
+                    gl::SamplerParameteri(self.id, gl::TEXTURE_COMPARE_FUNC, f as GLint);
+                },
+                None => gl::SamplerParameteri(self.id, gl::TEXTURE_COMPARE_MODE, gl::NONE as GLint)
+            }
         }
     }
 
 }
 
-pub struct SubTexture<'a, T:Texture> {
-    tex: &'a T,
-    offset: T::Dim,
-    dim: T::Dim
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteSamplers(1, &self.id as *const GLuint); }
+    }
 }
 
-pub struct SubTextureMut<'a, T:Texture> {
-    tex: &'a mut T,
-    offset: T::Dim,
-    dim: T::Dim
+///
+///Binds a texture and sampler to the given texture unit, where a sampler of 0 reads the texture
+///with the texture's own parameters
+///
+pub unsafe fn bind_texture_unit(unit: GLuint, target: TextureTarget, texture: GLuint, sampler: GLuint) {
+    gl::ActiveTexture(gl::TEXTURE0 + unit);
+    gl::BindTexture(target as GLenum, texture);
+    if gl::BindSampler::is_loaded() { gl::BindSampler(unit, sampler); }
 }
 
-impl<'a,T:Texture> SubTextureMut<'a,T> {
-    pub fn sub_image<P:PixelData<T::PixelFormat>>(&mut self, pixels:P) {
-        unsafe {
+///
+///A level of a texture bound to an image unit for loads and stores from a shader
+///
+///The access is given by a [BufferAccess] marker, so images that are only read can be made from a
+///shared borrow of the texture while ones that are written need a mutable borrow.
+///
+pub struct Image<'a, I:GLSLImage, A:BufferAccess> {
+    texture: &'a Texture<I::Format>,
+    level: usize,
+    access: PhantomData<(I, A)>
+}
 
-            let mut target = T::target().as_loc();
-            let mut buf_target = buffer_new::BufferTarget::PixelPackBuffer.as_loc();
-
-            let binding = target.bind_raw(self.tex.id()).unwrap();
-            let buf_binding = pixels.bind_pixel_buffer(&mut buf_target);
-
-            let (fmt, ty) = pixels.format_type().format_type();
-            let (x,y,z) = (
-                self.offset.width() as GLsizei,
-                self.offset.height() as GLsizei,
-                self.offset.depth() as GLsizei
-            );
-            let (w,h,d) = (
-                self.dim.width() as GLsizei,
-                self.dim.height() as GLsizei,
-                self.dim.depth() as GLsizei
-            );
-
-            let dim = T::Dim::dim();
-            if cfg!(debug_assertions) {
-                let len = if dim == 3 {w*h*d} else if dim == 2 {w*h} else {w};
-                if len != pixels.len() as GLsizei {
-                    panic!("invalid number of pixels for dimensions ({},{},{})",w,h,d);
-                }
-            }
+impl<'a, I:GLSLImage, A:BufferAccess> Image<'a, I, A> {
 
-            apply_packing_settings(&pixels);
-            match dim {
-                1 => gl::TexSubImage1D(
-                    binding.target_id(), 0, x, w,
-                    fmt.into(), ty as GLenum, pixels.pixels()
-                ),
-                2 => gl::TexSubImage2D(
-                    binding.target_id(), 0, x,y, w,h,
-                    fmt.into(), ty as GLenum, pixels.pixels()
-                ),
-                3 => gl::TexSubImage3D(
-                    binding.target_id(), 0, x,y,z, w,h,d,
-                    fmt.into(), ty as GLenum, pixels.pixels()
-                ),
-                _ => panic!("{}D textures not supported", T::Dim::dim())
-            };
-            drop(buf_binding);
+    fn new(texture: &'a Texture<I::Format>) -> Result<Self, GLError> {
+        if texture.target() != I::target() {
+            return Err(GLError::InvalidOperation(format!(
+                "An image of a {} can't be made from a {}", I::target(), texture.target()
+            )));
         }
+        Ok(Image { texture: texture, level: 0, access: PhantomData })
+    }
+
+    ///Selects the mipmap level to bind instead of the base level
+    pub fn level(mut self, level: usize) -> Self {
+        assert!(level < self.texture.levels(), "Mipmap level {} out of range", level);
+        self.level = level;
+        self
     }
+
+    #[inline] pub fn texture(&self) -> &'a Texture<I::Format> { self.texture }
+
+    ///
+    ///Binds every layer of this image to the given unit, read as `format`
+    ///
+    ///Image formats only need to match the texture's format in size, so that's all that gets checked
+    ///
+    pub unsafe fn bind(&self, unit: GLuint, format: I::Format) {
+        debug_assert_eq!(
+            format_size(format.into()), format_size(self.texture.format().into()),
+            "Image format {} is a different size than the texture format {}", format, self.texture.format()
+        );
+
+        let access = match (<A::Read as Boolean>::VALUE, <A::Write as Boolean>::VALUE) {
+            (true, false) => gl::READ_ONLY,
+            (false, true) => gl::WRITE_ONLY,
+            _ => gl::READ_WRITE
+        };
+        gl::BindImageTexture(
+            unit, self.texture.id(), self.level as GLint, gl::TRUE, 0, access, format.into()
+        );
+    }
+
+}
+
+impl<'a, I:GLSLImage> TryFrom<&'a Texture<I::Format>> for Image<'a, I, Read> {
+    type Error = GLError;
+    #[inline] fn try_from(tex: &'a Texture<I::Format>) -> Result<Self, GLError> { Self::new(tex) }
+}
+
+impl<'a, I:GLSLImage, A:WriteAccess> TryFrom<&'a mut Texture<I::Format>> for Image<'a, I, A> {
+    type Error = GLError;
+    #[inline] fn try_from(tex: &'a mut Texture<I::Format>) -> Result<Self, GLError> { Self::new(tex) }
+}
+
+///Removes any image from the given image unit
+pub unsafe fn unbind_image(unit: GLuint) {
+    gl::BindImageTexture(unit, 0, 0, gl::FALSE, 0, gl::READ_ONLY, gl::R8);
 }
 
+///
+///The format layout qualifiers of GLSL images, as used by [glsl!] to bind each [Image]
+///
+#[allow(non_upper_case_globals)]
+pub mod image_format {
+    use super::*;
 
-// pub struct Tex1D<F:InternalFormat> {
-//     raw: RawTex,
-//     format: F,
-//     dim: [usize;1]
-// }
-//
-//
-//
-// pub struct Tex2D<F:InternalFormat> {
-//     raw: RawTex,
-//     format: F,
-//     dim: [usize;2]
-// }
-//
-// pub struct Tex3D<F:InternalFormat> {
-//     raw: RawTex,
-//     format: F,
-//     dim: [usize;3]
-// }
+    pub const rgba32f: InternalFormatFloat = InternalFormatFloat::RGBA32F;
+    pub const rgba16f: InternalFormatFloat = InternalFormatFloat::RGBA16F;
+    pub const rg32f: InternalFormatFloat = InternalFormatFloat::RG32F;
+    pub const rg16f: InternalFormatFloat = InternalFormatFloat::RG16F;
+    pub const r11f_g11f_b10f: InternalFormatFloat = InternalFormatFloat::R11F_G11F_B10F;
+    pub const r32f: InternalFormatFloat = InternalFormatFloat::R32F;
+    pub const r16f: InternalFormatFloat = InternalFormatFloat::R16F;
+    pub const rgba16: InternalFormatFloat = InternalFormatFloat::RGBA16;
+    pub const rgb10_a2: InternalFormatFloat = InternalFormatFloat::RGB10_A2;
+    pub const rgba8: InternalFormatFloat = InternalFormatFloat::RGBA8;
+    pub const rg16: InternalFormatFloat = InternalFormatFloat::RG16;
+    pub const rg8: InternalFormatFloat = InternalFormatFloat::RG8;
+    pub const r16: InternalFormatFloat = InternalFormatFloat::R16;
+    pub const r8: InternalFormatFloat = InternalFormatFloat::R8;
+    pub const rgba16_snorm: InternalFormatFloat = InternalFormatFloat::RGBA16_SNORM;
+    pub const rgba8_snorm: InternalFormatFloat = InternalFormatFloat::RGBA8_SNORM;
+    pub const rg16_snorm: InternalFormatFloat = InternalFormatFloat::RG16_SNORM;
+    pub const rg8_snorm: InternalFormatFloat = InternalFormatFloat::RG8_SNORM;
+    pub const r16_snorm: InternalFormatFloat = InternalFormatFloat::R16_SNORM;
+    pub const r8_snorm: InternalFormatFloat = InternalFormatFloat::R8_SNORM;
+
+    pub const rgba32i: InternalFormatInt = InternalFormatInt::RGBA32I;
+    pub const rgba16i: InternalFormatInt = InternalFormatInt::RGBA16I;
+    pub const rgba8i: InternalFormatInt = InternalFormatInt::RGBA8I;
+    pub const rg32i: InternalFormatInt = InternalFormatInt::RG32I;
+    pub const rg16i: InternalFormatInt = InternalFormatInt::RG16I;
+    pub const rg8i: InternalFormatInt = InternalFormatInt::RG8I;
+    pub const r32i: InternalFormatInt = InternalFormatInt::R32I;
+    pub const r16i: InternalFormatInt = InternalFormatInt::R16I;
+    pub const r8i: InternalFormatInt = InternalFormatInt::R8I;
+
+    pub const rgba32ui: InternalFormatUInt = InternalFormatUInt::RGBA32UI;
+    pub const rgba16ui: InternalFormatUInt = InternalFormatUInt::RGBA16UI;
+    pub const rgb10_a2ui: InternalFormatUInt = InternalFormatUInt::RGB10_A2UI;
+    pub const rgba8ui: InternalFormatUInt = InternalFormatUInt::RGBA8UI;
+    pub const rg32ui: InternalFormatUInt = InternalFormatUInt::RG32UI;
+    pub const rg16ui: InternalFormatUInt = InternalFormatUInt::RG16UI;
+    pub const rg8ui: InternalFormatUInt = InternalFormatUInt::RG8UI;
+    pub const r32ui: InternalFormatUInt = InternalFormatUInt::R32UI;
+    pub const r16ui: InternalFormatUInt = InternalFormatUInt::R16UI;
+    pub const r8ui: InternalFormatUInt = InternalFormatUInt::R8UI;
+}
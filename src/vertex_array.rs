@@ -0,0 +1,158 @@
+use super::*;
+
+use std::marker::PhantomData;
+
+glenum! {
+    pub enum VertexArrayTarget {
+        [VertexArray VERTEX_ARRAY "Vertex Array"]
+    }
+}
+
+unsafe impl Target for VertexArrayTarget {
+    type Resource = RawVertexArray;
+    #[inline] unsafe fn bind(self, id: GLuint) { gl::BindVertexArray(id) }
+}
+
+///A raw OpenGL Vertex Array Object with no particular attribute setup
+#[repr(C)]
+pub struct RawVertexArray(GLuint);
+
+unsafe impl Resource for RawVertexArray {
+
+    type GL = GLProvider;
+    type BindingTarget = VertexArrayTarget;
+
+    #[inline] fn id(&self) -> GLuint { self.0 }
+
+    #[inline]
+    fn into_raw(self) -> GLuint {
+        let id = self.id();
+        ::std::mem::forget(self);
+        id
+    }
+
+    #[inline]
+    unsafe fn from_raw(id: GLuint) -> Option<Self> {
+        if Self::is(id) { Some(RawVertexArray(id)) } else { None }
+    }
+
+    #[inline]
+    fn gen(_gl: &Self::GL) -> Self {
+        let mut id: GLuint = 0;
+        unsafe { gl::GenVertexArrays(1, &mut id as *mut GLuint); }
+        RawVertexArray(id)
+    }
+
+    #[inline]
+    fn gen_resources(_gl: &Self::GL, count: GLuint) -> Box<[Self]> {
+        let mut ids: Vec<GLuint> = vec![0; count as usize];
+        if count > 0 {
+            unsafe { gl::GenVertexArrays(count as GLsizei, &mut ids[0] as *mut GLuint); }
+        }
+        ids.into_iter().map(|id| RawVertexArray(id)).collect()
+    }
+
+    #[inline] fn is(id: GLuint) -> bool { unsafe { gl::IsVertexArray(id) != gl::FALSE } }
+
+    #[inline]
+    fn delete(self) {
+        unsafe { gl::DeleteVertexArrays(1, &self.into_raw() as *const GLuint); }
+    }
+
+    #[inline]
+    fn delete_resources(resources: Box<[Self]>) {
+        let ids: Vec<GLuint> = resources.into_vec().into_iter().map(|vao| vao.into_raw()).collect();
+        if ids.len() > 0 {
+            unsafe { gl::DeleteVertexArrays(ids.len() as GLsizei, &ids[0] as *const GLuint); }
+        }
+    }
+
+}
+
+impl Drop for RawVertexArray {
+    #[inline] fn drop(&mut self) { Resource::delete(RawVertexArray(self.0)); }
+}
+
+impl RawVertexArray {
+    #[inline] pub unsafe fn bind(&self) { VertexArrayTarget::VertexArray.bind(self.0) }
+    #[inline] pub unsafe fn unbind() { VertexArrayTarget::VertexArray.bind(0) }
+}
+
+///
+///A Vertex Array Object with the attributes of a program already loaded into it
+///
+///Since the VAO keeps a reference to each of the buffers used for its attribute arrays, it borrows
+///them for as long as it exists. Do note however, that any [constant](Attribute::Value) attributes
+///are _not_ part of the VAO state and are simply set once when the VAO is built.
+///
+///The VAO can be used with any program whose attributes have the same locations as the one it was
+///built for.
+///
+pub struct VertexArray<'a> {
+    raw: RawVertexArray,
+    locations: Vec<GLint>,
+    p: PhantomData<&'a ()>
+}
+
+impl<'a> VertexArray<'a> {
+
+    ///
+    ///Creates an empty VAO
+    ///
+    ///# Unsafety
+    ///
+    ///It is up to the caller to make sure that the attributes loaded into this object are the ones
+    ///expected by whatever program it is drawn with
+    ///
+    pub unsafe fn new(gl: &GLProvider) -> Self {
+        VertexArray { raw: RawVertexArray::gen(gl), locations: Vec::new(), p: PhantomData }
+    }
+
+    ///Loads an attribute into the given location of this VAO
+    pub unsafe fn load<A:GLSLType>(&mut self, loc: &AttributeLocation, a: Attribute<'a, A>) {
+        self.raw.bind();
+        loc.load(&a);
+        RawVertexArray::unbind();
+        self.locations.push(loc.id());
+    }
+
+    ///Sets the format of an attribute and the binding index it reads its vertex buffer from
+    pub unsafe fn load_format<A:GLSLType>(&mut self, loc: &AttributeLocation, b: AttribBinding<A>) {
+        self.raw.bind();
+        loc.load_format(&b);
+        RawVertexArray::unbind();
+        self.locations.push(loc.id());
+    }
+
+    ///Checks if the attributes of this VAO were loaded into exactly the given locations
+    pub fn has_layout(&self, locations: &[GLint]) -> bool {
+        let mut l1 = self.locations.clone();
+        let mut l2 = locations.to_vec();
+        l1.sort();
+        l2.sort();
+        l1 == l2
+    }
+
+    ///
+    ///Binds the buffer of the given array to a vertex buffer binding index, keeping all of the
+    ///attribute formats that read from it
+    ///
+    ///This way, switching between meshes with the same layout only requires rebinding their buffers.
+    ///
+    pub fn bind_vertex_buffer<A:GLSLType>(&mut self, binding: GLuint, arr: AttribArray<'a, A>) {
+        unsafe {
+            self.raw.bind();
+            gl::BindVertexBuffer(
+                binding, arr.buffer().id(), arr.buffer().data_offset() as GLintptr, arr.stride() as GLsizei
+            );
+            gl::VertexBindingDivisor(binding, arr.divisor());
+            RawVertexArray::unbind();
+        }
+    }
+
+    #[inline] pub unsafe fn bind(&self) { self.raw.bind() }
+    #[inline] pub unsafe fn unbind() { RawVertexArray::unbind() }
+
+    #[inline] pub fn id(&self) -> GLuint { self.raw.id() }
+
+}